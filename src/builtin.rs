@@ -0,0 +1,170 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+use crate::error::{BuiltInCommandError, BuiltInCommandErrorKind};
+use crate::store::Store;
+use crate::template;
+
+type BuiltInResult<T> = Result<T, BuiltInCommandError>;
+
+fn error(kind: BuiltInCommandErrorKind, message: String) -> BuiltInCommandError {
+    BuiltInCommandError::with_kind(kind, &message)
+}
+
+fn io_error(action: &str, path: &Path, err: io::Error) -> BuiltInCommandError {
+    let kind = match err.kind() {
+        ErrorKind::NotFound => BuiltInCommandErrorKind::NotFound,
+        ErrorKind::AlreadyExists => BuiltInCommandErrorKind::AlreadyExists,
+        _ => BuiltInCommandErrorKind::Io,
+    };
+    error(kind, format!("{}: {}: {}", action, path.display(), err))
+}
+
+/// Returns the `index`-th argument expanded as a template, or the value of `default_var`.
+fn path_arg<T: Store>(
+    store: &T,
+    cwd: Option<&Path>,
+    args: &[String],
+    index: usize,
+    default_var: &str,
+) -> BuiltInResult<PathBuf> {
+    let path = match args.get(index) {
+        Some(arg) => template::expand_template(store, arg),
+        None => store.get(default_var).unwrap_or_default().to_owned(),
+    };
+    if path.is_empty() {
+        return Err(error(
+            BuiltInCommandErrorKind::InvalidArgument,
+            format!("path is empty (argument {} or {})", index + 1, default_var),
+        ));
+    }
+    Ok(match cwd {
+        Some(cwd) => cwd.join(path),
+        None => PathBuf::from(path),
+    })
+}
+
+/// Runs a built-in command. `args[0]` is its name; the remaining arguments override the default paths.
+/// Returns the text the command outputs, if any.
+pub fn run<T: Store>(store: &T, cwd: Option<&Path>, args: &[String]) -> BuiltInResult<Option<String>> {
+    let name = args.first().map(|name| name.as_str()).unwrap_or_default();
+    let args = args.get(1..).unwrap_or_default();
+    match name {
+        "mkdir" => {
+            let path = path_arg(store, cwd, args, 0, "DEPOT_LOCAL_PATH")?;
+            mkdir(&path)?;
+            Ok(None)
+        }
+        "move" => {
+            let source = path_arg(store, cwd, args, 0, "DEPOT_SOURCE_LOCAL_PATH")?;
+            let destination = path_arg(store, cwd, args, 1, "DEPOT_LOCAL_PATH")?;
+            move_path(&source, &destination)?;
+            Ok(None)
+        }
+        "print-path" => {
+            let path = path_arg(store, cwd, args, 0, "DEPOT_LOCAL_PATH")?;
+            Ok(Some(path.to_string_lossy().into_owned()))
+        }
+        "remove" => {
+            let path = path_arg(store, cwd, args, 0, "DEPOT_LOCAL_PATH")?;
+            let root = store.get("DEPOT_ROOT_PATH").map(Path::new);
+            remove(&path, root)?;
+            Ok(None)
+        }
+        _ => Err(error(
+            BuiltInCommandErrorKind::InvalidArgument,
+            format!("unknown builtin: {}", name),
+        )),
+    }
+}
+
+fn mkdir(path: &Path) -> BuiltInResult<()> {
+    if path.exists() && !path.is_dir() {
+        return Err(error(
+            BuiltInCommandErrorKind::AlreadyExists,
+            format!("mkdir: {}: exists and is not a directory", path.display()),
+        ));
+    }
+    fs::create_dir_all(path).map_err(|err| io_error("mkdir", path, err))
+}
+
+/// Renames `source` to `destination`, creating its parents; falls back to copy and delete across devices.
+pub fn move_path(source: &Path, destination: &Path) -> BuiltInResult<()> {
+    if fs::symlink_metadata(source).is_err() {
+        return Err(error(
+            BuiltInCommandErrorKind::NotFound,
+            format!("move: {}: source does not exist", source.display()),
+        ));
+    }
+    if fs::symlink_metadata(destination).is_ok() {
+        return Err(error(
+            BuiltInCommandErrorKind::AlreadyExists,
+            format!("move: {}: destination already exists", destination.display()),
+        ));
+    }
+    if destination.starts_with(source) {
+        return Err(error(
+            BuiltInCommandErrorKind::InvalidArgument,
+            format!(
+                "move: {}: destination is inside the source {}",
+                destination.display(),
+                source.display()
+            ),
+        ));
+    }
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent).map_err(|err| io_error("move", parent, err))?;
+    }
+    match fs::rename(source, destination) {
+        Ok(()) => Ok(()),
+        Err(err) if err.raw_os_error() == Some(libc::EXDEV) => {
+            // cross-device: copy everything, then delete the source only once the copy is complete
+            if let Err(err) = copy_recursive(source, destination) {
+                let _ = remove_any(destination);
+                return Err(io_error("move", destination, err));
+            }
+            remove_any(source).map_err(|err| io_error("move", source, err))
+        }
+        Err(err) => Err(io_error("move", source, err)),
+    }
+}
+
+fn copy_recursive(source: &Path, destination: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+    if metadata.file_type().is_symlink() {
+        symlink(fs::read_link(source)?, destination)
+    } else if metadata.is_dir() {
+        fs::create_dir(destination)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+        fs::set_permissions(destination, metadata.permissions())
+    } else {
+        fs::copy(source, destination).map(|_| ())
+    }
+}
+
+fn remove_any(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Removes a file or directory tree, refusing the depot root and `/`.
+pub fn remove(path: &Path, root: Option<&Path>) -> BuiltInResult<()> {
+    let is_root = root.is_some_and(|root| {
+        fs::canonicalize(root).ok().is_some_and(|root| fs::canonicalize(path).ok() == Some(root))
+    });
+    if is_root || path.parent().is_none() {
+        return Err(error(
+            BuiltInCommandErrorKind::InvalidArgument,
+            format!("remove: {}: refusing to remove the root directory", path.display()),
+        ));
+    }
+    remove_any(path).map_err(|err| io_error("remove", path, err))
+}
@@ -5,18 +5,31 @@ use std::io::prelude::*;
 use std::path::PathBuf;
 use toml;
 
+use crate::utils;
+
+pub mod archive_format;
 pub mod behavior;
+pub mod checks;
+pub mod classifiers;
+pub mod conflict;
 pub mod directory_condition;
+pub mod duration;
 pub mod fields_definition;
 pub mod generator;
 pub mod overload;
 pub mod parse;
 pub mod pattern;
+pub mod presets;
 pub mod resolve;
 pub mod shell;
+pub mod step;
 
 use self::{
+    archive_format::ArchiveFormat,
     behavior::Behavior,
+    checks::Checks,
+    classifiers::Classifiers,
+    conflict::ConflictPolicy,
     directory_condition::DirectoryCondition,
     fields_definition::FieldsDefinition,
     generator::Generator,
@@ -29,6 +42,8 @@ use self::{
 
 #[derive(Deserialize)]
 pub struct Config {
+    #[serde(default)]
+    pub presets: Vec<String>,
     pub core: Core,
     #[serde(default)]
     pub shell: Shell,
@@ -52,17 +67,33 @@ pub struct Subcommands {
     pub mv: Move,
     #[serde(default)]
     pub list: List,
+    #[serde(default)]
+    pub update: Update,
+    #[serde(default)]
+    pub status: Status,
+    #[serde(default)]
+    pub archive: Archive,
 }
 
 pub type Get = Overloadable<GetParams>;
 #[derive(Deserialize)]
 pub struct GetParams {
     pub command: Behavior,
+    #[serde(default)]
+    pub on_conflict: ConflictPolicy,
+    #[serde(default)]
+    pub update_command: Behavior,
+    /// Runs `command` against `DEPOT_STAGING_PATH` and renames it into place on success.
+    #[serde(default)]
+    pub staging: bool,
 }
 impl Default for GetParams {
     fn default() -> Self {
         Self {
             command: Default::default(),
+            on_conflict: Default::default(),
+            update_command: Default::default(),
+            staging: false,
         }
     }
 }
@@ -75,27 +106,71 @@ pub struct MoveParams {
     #[serde(default = "behavior::nop")]
     pub pre_command: Behavior,
     pub command: Behavior,
+    #[serde(default)]
+    pub on_conflict: ConflictPolicy,
+    #[serde(default)]
+    pub update_command: Behavior,
 }
 impl Default for MoveParams {
     fn default() -> Self {
         Self {
             pre_command: Default::default(),
             command: Default::default(),
+            on_conflict: Default::default(),
+            update_command: Default::default(),
         }
     }
 }
 
+pub type Update = Overloadable<UpdateParams>;
+#[derive(Deserialize, Default)]
+pub struct UpdateParams {
+    #[serde(default)]
+    pub command: Behavior,
+}
+
+pub type Status = Overloadable<StatusParams>;
+#[derive(Deserialize, Default)]
+pub struct StatusParams {
+    #[serde(default)]
+    pub checks: Checks,
+}
+
+#[derive(Deserialize)]
+pub struct Archive {
+    /// Where archived projects are kept; skipped by the `list` walk.
+    #[serde(default = "default_archive_root")]
+    pub root: Generator,
+    #[serde(default)]
+    pub format: ArchiveFormat,
+}
+impl Default for Archive {
+    fn default() -> Self {
+        Self {
+            root: default_archive_root(),
+            format: Default::default(),
+        }
+    }
+}
+fn default_archive_root() -> Generator {
+    Generator::Template(format!("${{DEPOT_ROOT_PATH}}/{}/archive", utils::STATE_DIR_NAME))
+}
+
 #[derive(Deserialize)]
 pub struct List {
+    #[serde(default)]
     pub project: Project,
     #[serde(default)]
     pub fields: FieldsDefinition,
+    #[serde(default)]
+    pub classifiers: Classifiers,
 }
 impl Default for List {
     fn default() -> Self {
         Self {
             project: Default::default(),
             fields: Default::default(),
+            classifiers: Default::default(),
         }
     }
 }
@@ -104,15 +179,28 @@ impl Default for List {
 pub struct Project {
     pub condition: DirectoryCondition,
     pub excludes: Vec<Pattern>,
+    /// Symlinked directories are followed unless this is turned off; loops are detected either way.
+    #[serde(default = "default_follow_symlinks")]
+    pub follow_symlinks: bool,
+    #[serde(default)]
+    pub nested_projects: bool,
+    #[serde(default)]
+    pub global_gitignore: bool,
 }
 impl Default for Project {
     fn default() -> Self {
         Self {
             condition: Default::default(),
             excludes: Default::default(),
+            follow_symlinks: default_follow_symlinks(),
+            nested_projects: false,
+            global_gitignore: false,
         }
     }
 }
+fn default_follow_symlinks() -> bool {
+    true
+}
 
 pub fn load_from_file(config_file: PathBuf) -> Result<Config, Box<dyn Error>> {
     let mut f = File::open(config_file)?;
@@ -120,7 +208,48 @@ pub fn load_from_file(config_file: PathBuf) -> Result<Config, Box<dyn Error>> {
     let mut contents = String::new();
     f.read_to_string(&mut contents)?;
 
-    let config: Config = toml::from_str(&contents)?;
+    #[derive(Deserialize)]
+    struct PresetNames {
+        #[serde(default)]
+        presets: Vec<String>,
+    }
+    let preset_names: PresetNames = toml::from_str(&contents)?;
+    if preset_names.presets.is_empty() {
+        let config: Config = toml::from_str(&contents)?;
+        return Ok(config);
+    }
+
+    let mut value: toml::Value = toml::from_str(&contents)?;
+    presets::apply(&mut value, &preset_names.presets).map_err(|err| -> Box<dyn Error> { err })?;
+    let config: Config = value.try_into()?;
 
     return Ok(config);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn default_config_recognizes_git_checkouts() {
+        let config: Config = toml::from_str(include_str!("../files/default.depotconfig.toml"))
+            .unwrap_or_else(|err| panic!("{}", err));
+
+        let root = std::env::temp_dir().join(format!("depot-config-test-{}", process::id()));
+        let checkout = root.join("host/user/repo");
+        let plain = root.join("host/user/plain");
+        fs::create_dir_all(checkout.join(".git")).unwrap();
+        fs::create_dir_all(&plain).unwrap();
+        let overloads = &config.overloads;
+        let checkout_overload = overloads.find_overload_for_directory(&root, &checkout);
+        let plain_overload = overloads.find_overload_for_directory(&root, &plain);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(checkout_overload.map(|overload| overload.name.as_str()), Some("git"));
+        assert!(plain_overload.is_none());
+        assert_eq!(config.subcommands.update.find_params_name(checkout_overload), Some("git"));
+    }
+}
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// How `archive` stores a project.
+#[derive(Deserialize, Serialize, clap::ArgEnum, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ArchiveFormat {
+    /// Move the directory as it is
+    #[default]
+    #[serde(alias = "directory")]
+    Directory,
+    /// Compress the directory into a `.tar.gz` file
+    #[serde(alias = "tar.gz", alias = "tgz")]
+    TarGz,
+}
+impl ArchiveFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            ArchiveFormat::Directory => "directory",
+            ArchiveFormat::TarGz => "tar.gz",
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use serde::{
-    de::{self, SeqAccess, Unexpected, Visitor},
+    de::{self, value::MapAccessDeserializer, MapAccess, SeqAccess, Unexpected, Visitor},
     Deserialize,
 };
 
 use crate::{
+    config::step::Step,
+    exit_code, runner,
     store::Store,
     template,
     utils::{CommandGenerator, GenericResult},
@@ -15,7 +17,9 @@ pub fn nop() -> Behavior {
 
 pub enum Behavior {
     Template(String),
-    Shell(Vec<String>),
+    Shell(Vec<Step>),
+    Exec(Vec<String>),
+    BuiltIn(Vec<String>),
     Nop,
     NotSupported,
 }
@@ -41,23 +45,41 @@ impl<'de> Deserialize<'de> for Behavior {
             where
                 E: de::Error,
             {
-                Ok(Self::Value::Shell(vec![v.to_owned()]))
+                Ok(Self::Value::Shell(vec![Step::shell(v.to_owned())]))
             }
 
             fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
             where
                 E: de::Error,
             {
-                Ok(Self::Value::Shell(vec![v]))
+                Ok(Self::Value::Shell(vec![Step::shell(v)]))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let step = Step::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(Self::Value::Shell(vec![step]))
             }
 
             fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
             where
                 V: SeqAccess<'de>,
             {
-                let method = seq
-                    .next_element::<String>()?
-                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let method = match seq
+                    .next_element::<SeqHead>()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?
+                {
+                    SeqHead::Method(method) => method,
+                    SeqHead::Step(step) => {
+                        let mut steps = vec![step];
+                        while let Some(step) = seq.next_element::<Step>()? {
+                            steps.push(step);
+                        }
+                        return Ok(Self::Value::Shell(steps));
+                    }
+                };
                 match method.as_str() {
                     "template" => {
                         let pattern = seq
@@ -66,14 +88,34 @@ impl<'de> Deserialize<'de> for Behavior {
                         Ok(Self::Value::Template(pattern))
                     }
                     "shell" => {
-                        let mut commands = Vec::<String>::new();
-                        while let Some(command) = seq.next_element::<String>()? {
-                            commands.push(command);
+                        let mut steps = Vec::<Step>::new();
+                        while let Some(step) = seq.next_element::<Step>()? {
+                            steps.push(step);
+                        }
+                        if steps.len() < 1 {
+                            return Err(de::Error::invalid_length(1, &self));
+                        }
+                        Ok(Self::Value::Shell(steps))
+                    }
+                    "exec" => {
+                        let mut args = Vec::<String>::new();
+                        while let Some(arg) = seq.next_element::<String>()? {
+                            args.push(arg);
+                        }
+                        if args.is_empty() {
+                            return Err(de::Error::invalid_length(1, &self));
                         }
-                        if commands.len() < 1 {
+                        Ok(Self::Value::Exec(args))
+                    }
+                    "builtin" => {
+                        let mut args = Vec::<String>::new();
+                        while let Some(arg) = seq.next_element::<String>()? {
+                            args.push(arg);
+                        }
+                        if args.is_empty() {
                             return Err(de::Error::invalid_length(1, &self));
                         }
-                        Ok(Self::Value::Shell(commands))
+                        Ok(Self::Value::BuiltIn(args))
                     }
                     "nop" => Ok(Self::Value::Nop),
                     "not-supported" => Ok(Self::Value::NotSupported),
@@ -86,6 +128,43 @@ impl<'de> Deserialize<'de> for Behavior {
     }
 }
 
+enum SeqHead {
+    Method(String),
+    Step(Step),
+}
+impl<'de> Deserialize<'de> for SeqHead {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct SeqHeadVisitor;
+        impl<'de> Visitor<'de> for SeqHeadVisitor {
+            type Value = SeqHead;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "behavior method or step")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Self::Value::Method(v.to_owned()))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let step = Step::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(Self::Value::Step(step))
+            }
+        }
+
+        deserializer.deserialize_any(SeqHeadVisitor)
+    }
+}
+
 impl Behavior {
     fn execute_template<T: Store>(store: &T, template: &str) -> GenericResult<i32> {
         let str = template::expand_template(store, template);
@@ -95,41 +174,61 @@ impl Behavior {
 
     fn execute_shell<Tc: CommandGenerator, Ts: Store>(
         cmdgen: &Tc,
-        store: &Ts,
-        commands: &Vec<String>,
+        store: &mut Ts,
+        steps: &Vec<Step>,
     ) -> GenericResult<i32> {
         let mut last_code: i32 = 0;
-        for command in commands {
-            last_code = cmdgen
-                .generate(store)
-                .arg(command)
-                .status()?
-                .code()
-                .unwrap_or(0);
+        for step in steps {
+            if !step.is_enabled(store) {
+                continue;
+            }
+            last_code = step.execute(cmdgen, store)?;
             if last_code != 0 {
-                break;
+                if step.allow_failure && !runner::is_interrupted() {
+                    last_code = 0;
+                } else {
+                    break;
+                }
             }
         }
         Ok(last_code)
     }
 
+    fn execute_exec<Tc: CommandGenerator, Ts: Store>(
+        cmdgen: &Tc,
+        store: &mut Ts,
+        args: &[String],
+    ) -> GenericResult<i32> {
+        Step::exec(args.to_vec()).execute(cmdgen, store)
+    }
+
+    fn execute_builtin<Tc: CommandGenerator, Ts: Store>(
+        cmdgen: &Tc,
+        store: &mut Ts,
+        args: &[String],
+    ) -> GenericResult<i32> {
+        Step::builtin(args.to_vec()).execute(cmdgen, store)
+    }
+
     fn execute_nop() -> GenericResult<i32> {
         Ok(0)
     }
 
     fn execute_not_supported() -> GenericResult<i32> {
         println!("not supported");
-        Ok(255)
+        Ok(exit_code::NOT_SUPPORTED)
     }
 
     pub fn execute<Tc: CommandGenerator, Ts: Store>(
         &self,
         cmdgen: &Tc,
-        store: &Ts,
+        store: &mut Ts,
     ) -> GenericResult<i32> {
         match self {
             Behavior::Template(template) => Self::execute_template(store, template),
             Behavior::Shell(commands) => Self::execute_shell(cmdgen, store, commands),
+            Behavior::Exec(args) => Self::execute_exec(cmdgen, store, args),
+            Behavior::BuiltIn(args) => Self::execute_builtin(cmdgen, store, args),
             Behavior::Nop => Self::execute_nop(),
             Behavior::NotSupported => Self::execute_not_supported(),
         }
@@ -0,0 +1,52 @@
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserialize,
+};
+
+use super::generator::Generator;
+
+/// Named status checks in the order they are written in the config.
+/// A check passes when its value is empty, `0` or `false`; any other value is reported.
+#[derive(Default)]
+pub struct Checks {
+    checks: Vec<(String, Generator)>,
+}
+impl Checks {
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Generator)> {
+        self.checks
+            .iter()
+            .map(|(name, generator)| (name.as_str(), generator))
+    }
+
+    pub fn is_passing(value: &str) -> bool {
+        matches!(value, "" | "0" | "false")
+    }
+}
+impl<'de> Deserialize<'de> for Checks {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ChecksVisitor;
+        impl<'de> Visitor<'de> for ChecksVisitor {
+            type Value = Checks;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "table of checks")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut checks = Vec::new();
+                while let Some((name, generator)) = map.next_entry::<String, Generator>()? {
+                    checks.push((name, generator));
+                }
+                Ok(Checks { checks })
+            }
+        }
+
+        deserializer.deserialize_map(ChecksVisitor)
+    }
+}
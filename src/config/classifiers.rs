@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserialize,
+};
+
+use crate::utils::{Compilable, DirectoryMatcher, GenericResult};
+
+use super::directory_condition::{CompiledDirectoryCondition, DirectoryCondition};
+
+/// Named directory conditions telling what kind of project a directory is, e.g. `rust` or `node`.
+/// They are tried in the order they are written in the config and the first match names the kind.
+#[derive(Default)]
+pub struct Classifiers {
+    classifiers: Vec<(String, DirectoryCondition)>,
+}
+impl Compilable<CompiledClassifiers> for Classifiers {
+    fn compile(&self) -> GenericResult<CompiledClassifiers> {
+        let mut classifiers = Vec::new();
+        for (name, condition) in &self.classifiers {
+            classifiers.push((name.clone(), condition.compile()?));
+        }
+        Ok(CompiledClassifiers { classifiers })
+    }
+}
+impl<'de> Deserialize<'de> for Classifiers {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ClassifiersVisitor;
+        impl<'de> Visitor<'de> for ClassifiersVisitor {
+            type Value = Classifiers;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "table of classifiers")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut classifiers = Vec::new();
+                while let Some((name, condition)) = map.next_entry::<String, DirectoryCondition>()? {
+                    classifiers.push((name, condition));
+                }
+                Ok(Classifiers { classifiers })
+            }
+        }
+
+        deserializer.deserialize_map(ClassifiersVisitor)
+    }
+}
+
+pub struct CompiledClassifiers {
+    classifiers: Vec<(String, CompiledDirectoryCondition)>,
+}
+impl CompiledClassifiers {
+    /// Returns the name of the first classifier matching the project directory.
+    pub fn classify(&self, root: &Path, path: &Path) -> Option<&str> {
+        self.classifiers
+            .iter()
+            .find(|(_, condition)| condition.is_match(root, path))
+            .map(|(name, _)| name.as_str())
+    }
+}
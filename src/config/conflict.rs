@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+/// What `get`, `create` and `move` do when the local path already exists.
+#[derive(Deserialize, clap::ArgEnum, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Fail with the conflict exit code
+    #[default]
+    #[serde(alias = "error")]
+    Error,
+    /// Print the existing path and succeed
+    #[serde(alias = "skip")]
+    Skip,
+    /// Run `update_command` instead of `command`
+    #[serde(alias = "update")]
+    Update,
+    /// Remove the existing path after confirmation, then run `command`
+    #[serde(alias = "overwrite")]
+    Overwrite,
+}
@@ -1,11 +1,16 @@
+use std::fmt;
+use std::fs;
 use std::path::Path;
 
-use serde::Deserialize;
+use regex::Regex;
+use serde::{Deserialize, de::{self, Visitor, SeqAccess, MapAccess, value::MapAccessDeserializer}};
 
 use crate::utils::{StringMatcher, GenericResult, DirectoryMatcher, Compilable};
 
 use super::pattern::Pattern;
 
+/// Entries are evaluated in order and the first one that holds decides:
+/// `parent` and `exact` entries make the directory a project, `ignore` entries rule it out.
 #[derive(Deserialize)]
 #[serde(transparent)]
 pub struct DirectoryCondition {
@@ -17,7 +22,10 @@ impl Default for DirectoryCondition {
             entries: vec![
                 DirectoryConditionEntry {
                     mode: DirectoryConditionMode::Parent,
-                    pattern: Pattern::Glob("**/.git".to_owned()),
+                    predicate: DirectoryPredicate::Pattern {
+                        pattern: Pattern::Glob("**/.git".to_owned()),
+                        content: None,
+                    },
                 }
             ]
         }
@@ -25,47 +33,46 @@ impl Default for DirectoryCondition {
 }
 impl Compilable<CompiledDirectoryCondition> for DirectoryCondition {
     fn compile(&self) -> GenericResult<CompiledDirectoryCondition> {
-        let mut entries: Vec<CompiledDirectoryConditionEntry> = Vec::new();
-        for entry in &self.entries {
-            entries.push(entry.compile()?);
-        }
         Ok(CompiledDirectoryCondition {
-            entries,
+            entries: compile_entries(&self.entries)?,
+            fallbacks: Vec::new(),
         })
     }
 }
 
+fn compile_entries(entries: &[DirectoryConditionEntry]) -> GenericResult<Vec<CompiledDirectoryConditionEntry>> {
+    let mut compiled: Vec<CompiledDirectoryConditionEntry> = Vec::new();
+    for entry in entries {
+        compiled.push(entry.compile()?);
+    }
+    Ok(compiled)
+}
+
 pub struct CompiledDirectoryCondition {
     entries: Vec<CompiledDirectoryConditionEntry>,
+    fallbacks: Vec<CompiledDirectoryCondition>,
+}
+impl CompiledDirectoryCondition {
+    /// Consults `fallbacks` when none of the entries holds; an `ignore` entry still rules the directory out.
+    pub fn or_any(mut self, fallbacks: Vec<CompiledDirectoryCondition>) -> Self {
+        self.fallbacks.extend(fallbacks);
+        self
+    }
+
+    /// Returns `None` if no entry holds.
+    fn evaluate(&self, prefix: &Path, path: &Path) -> Option<bool> {
+        self.entries
+            .iter()
+            .find(|entry| entry.holds(prefix, path))
+            .map(|entry| !matches!(entry.mode, DirectoryConditionMode::Ignore))
+    }
 }
 impl DirectoryMatcher for CompiledDirectoryCondition {
     fn is_match(&self, prefix: &Path, path: &Path) -> bool {
-        for entry in &self.entries {
-            match entry.mode {
-                DirectoryConditionMode::Parent => {
-                    if let Ok(children) = path.read_dir() {
-                        for child in children {
-                            if let Ok(child) = child {
-                                if entry.is_match(prefix, &child.path()) {
-                                    return true;
-                                }
-                            }
-                        }
-                    }
-                },
-                DirectoryConditionMode::Exact => {
-                    if entry.is_match(prefix, path) {
-                        return true;
-                    }
-                },
-                DirectoryConditionMode::Ignore => {
-                    if entry.is_match(prefix, path) {
-                        return false;
-                    }
-                },
-            }
+        match self.evaluate(prefix, path) {
+            Some(is_match) => is_match,
+            None => self.fallbacks.iter().any(|fallback| fallback.is_match(prefix, path)),
         }
-        false
     }
 }
 
@@ -79,31 +86,238 @@ pub enum DirectoryConditionMode {
     Ignore,
 }
 
-#[derive(Deserialize)]
+/// What an entry tests. Only `pattern` distinguishes the modes: `parent` applies it to the children
+/// of the directory and `exact` to the directory itself.
+pub enum DirectoryPredicate {
+    Pattern { pattern: Pattern, content: Option<String> },
+    /// Every path, relative to the directory, exists (and is a file whose content matches, if given).
+    Contains { paths: Vec<String>, content: Option<String> },
+    /// Number of path components below the root, both ends inclusive.
+    Depth { min: Option<usize>, max: Option<usize> },
+    /// `HEAD` file and `objects/` directory, as in a bare git repository.
+    BareRepository(bool),
+    All(Vec<DirectoryConditionEntry>),
+    Any(Vec<DirectoryConditionEntry>),
+    Not(Box<DirectoryConditionEntry>),
+}
+
 pub struct DirectoryConditionEntry {
     pub mode: DirectoryConditionMode,
-    pub pattern: Pattern,
+    pub predicate: DirectoryPredicate,
 }
 impl Compilable<CompiledDirectoryConditionEntry> for DirectoryConditionEntry {
     fn compile(&self) -> GenericResult<CompiledDirectoryConditionEntry> {
+        let compile_content = |content: &Option<String>| content.as_deref().map(Regex::new).transpose();
+        let predicate = match &self.predicate {
+            DirectoryPredicate::Pattern { pattern, content } => CompiledDirectoryPredicate::Pattern {
+                pattern: pattern.compile()?,
+                content: compile_content(content)?,
+            },
+            DirectoryPredicate::Contains { paths, content } => CompiledDirectoryPredicate::Contains {
+                paths: paths.clone(),
+                content: compile_content(content)?,
+            },
+            DirectoryPredicate::Depth { min, max } => CompiledDirectoryPredicate::Depth { min: *min, max: *max },
+            DirectoryPredicate::BareRepository(expected) => CompiledDirectoryPredicate::BareRepository(*expected),
+            DirectoryPredicate::All(entries) => CompiledDirectoryPredicate::All(compile_entries(entries)?),
+            DirectoryPredicate::Any(entries) => CompiledDirectoryPredicate::Any(compile_entries(entries)?),
+            DirectoryPredicate::Not(entry) => CompiledDirectoryPredicate::Not(Box::new(entry.compile()?)),
+        };
         Ok(CompiledDirectoryConditionEntry {
             mode: self.mode,
-            pattern: self.pattern.compile()?
+            predicate,
         })
     }
 }
 
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DirectoryConditionTable {
+    mode: Option<DirectoryConditionMode>,
+    pattern: Option<Pattern>,
+    #[serde(default, deserialize_with = "deserialize_paths")]
+    contains: Option<Vec<String>>,
+    content: Option<String>,
+    depth: Option<DepthRange>,
+    bare_repository: Option<bool>,
+    all: Option<Vec<DirectoryConditionEntry>>,
+    any: Option<Vec<DirectoryConditionEntry>>,
+    not: Option<Box<DirectoryConditionEntry>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DepthRange {
+    min: Option<usize>,
+    max: Option<usize>,
+}
+
+/// Accepts a single path as well as a list of them.
+fn deserialize_paths<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct PathsVisitor;
+    impl<'de> Visitor<'de> for PathsVisitor {
+        type Value = Vec<String>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "path or list of paths")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(vec![v.to_owned()])
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut paths = Vec::new();
+            while let Some(path) = seq.next_element()? {
+                paths.push(path);
+            }
+            Ok(paths)
+        }
+    }
+    deserializer.deserialize_any(PathsVisitor).map(Some)
+}
+
+impl<'de> Deserialize<'de> for DirectoryConditionEntry {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct DirectoryConditionEntryVisitor;
+        impl<'de> Visitor<'de> for DirectoryConditionEntryVisitor {
+            type Value = DirectoryConditionEntry;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "directory condition")
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let table = DirectoryConditionTable::deserialize(MapAccessDeserializer::new(map))?;
+                let mut predicates = Vec::new();
+                if let Some(pattern) = table.pattern {
+                    predicates.push(DirectoryPredicate::Pattern { pattern, content: table.content.clone() });
+                }
+                if let Some(paths) = table.contains {
+                    predicates.push(DirectoryPredicate::Contains { paths, content: table.content.clone() });
+                }
+                if let Some(depth) = table.depth {
+                    predicates.push(DirectoryPredicate::Depth { min: depth.min, max: depth.max });
+                }
+                if let Some(expected) = table.bare_repository {
+                    predicates.push(DirectoryPredicate::BareRepository(expected));
+                }
+                if let Some(entries) = table.all {
+                    predicates.push(DirectoryPredicate::All(entries));
+                }
+                if let Some(entries) = table.any {
+                    predicates.push(DirectoryPredicate::Any(entries));
+                }
+                if let Some(entry) = table.not {
+                    predicates.push(DirectoryPredicate::Not(entry));
+                }
+
+                const KEYS: &str = "one of `pattern`, `contains`, `depth`, `bare_repository`, `all`, `any` or `not`";
+                if predicates.len() != 1 {
+                    return Err(de::Error::invalid_length(predicates.len(), &KEYS));
+                }
+                let predicate = predicates.remove(0);
+                if table.content.is_some()
+                    && !matches!(predicate, DirectoryPredicate::Pattern { .. } | DirectoryPredicate::Contains { .. })
+                {
+                    return Err(de::Error::custom("`content` requires `pattern` or `contains`"));
+                }
+                Ok(DirectoryConditionEntry {
+                    mode: table.mode.unwrap_or(DirectoryConditionMode::Exact),
+                    predicate,
+                })
+            }
+        }
+        deserializer.deserialize_map(DirectoryConditionEntryVisitor)
+    }
+}
+
+enum CompiledDirectoryPredicate {
+    Pattern { pattern: Box<dyn StringMatcher>, content: Option<Regex> },
+    Contains { paths: Vec<String>, content: Option<Regex> },
+    Depth { min: Option<usize>, max: Option<usize> },
+    BareRepository(bool),
+    All(Vec<CompiledDirectoryConditionEntry>),
+    Any(Vec<CompiledDirectoryConditionEntry>),
+    Not(Box<CompiledDirectoryConditionEntry>),
+}
+
 pub struct CompiledDirectoryConditionEntry {
     pub mode: DirectoryConditionMode,
-    pub pattern: Box<dyn StringMatcher>,
+    predicate: CompiledDirectoryPredicate,
 }
 impl CompiledDirectoryConditionEntry {
+    /// Whether the predicate holds for `path`, regardless of the mode.
+    pub fn holds(&self, prefix: &Path, path: &Path) -> bool {
+        match &self.predicate {
+            CompiledDirectoryPredicate::Pattern { pattern, content } => match self.mode {
+                DirectoryConditionMode::Parent => {
+                    path.read_dir().ok().is_some_and(|children| {
+                        children
+                            .filter_map(|child| child.ok())
+                            .any(|child| Self::is_pattern_match(pattern.as_ref(), content.as_ref(), prefix, &child.path()))
+                    })
+                },
+                DirectoryConditionMode::Exact | DirectoryConditionMode::Ignore => {
+                    Self::is_pattern_match(pattern.as_ref(), content.as_ref(), prefix, path)
+                },
+            },
+            CompiledDirectoryPredicate::Contains { paths, content } => paths.iter().all(|relpath| {
+                let child = path.join(relpath);
+                match content {
+                    Some(content) => Self::is_content_match(content, &child),
+                    None => fs::symlink_metadata(child).is_ok(),
+                }
+            }),
+            CompiledDirectoryPredicate::Depth { min, max } => {
+                let Ok(relpath) = path.strip_prefix(prefix) else {
+                    return false;
+                };
+                let depth = relpath.components().count();
+                min.is_none_or(|min| depth >= min) && max.is_none_or(|max| depth <= max)
+            },
+            CompiledDirectoryPredicate::BareRepository(expected) => {
+                (path.join("HEAD").is_file() && path.join("objects").is_dir()) == *expected
+            },
+            CompiledDirectoryPredicate::All(entries) => entries.iter().all(|entry| entry.is_match(prefix, path)),
+            CompiledDirectoryPredicate::Any(entries) => entries.iter().any(|entry| entry.is_match(prefix, path)),
+            CompiledDirectoryPredicate::Not(entry) => !entry.is_match(prefix, path),
+        }
+    }
+
+    /// Whether the entry counts as satisfied inside `all`, `any` and `not`: an `ignore` entry is satisfied
+    /// when its predicate does not hold.
     pub fn is_match(&self, prefix: &Path, path: &Path) -> bool {
-        if let Ok(path) = path.strip_prefix(prefix) {
-            if let Some(path) = path.to_str() {
-                return self.pattern.is_match(path);
-            }
+        match self.mode {
+            DirectoryConditionMode::Ignore => !self.holds(prefix, path),
+            _ => self.holds(prefix, path),
         }
-        false
+    }
+
+    fn is_pattern_match(pattern: &dyn StringMatcher, content: Option<&Regex>, prefix: &Path, path: &Path) -> bool {
+        let Some(relpath) = path.strip_prefix(prefix).ok().and_then(|path| path.to_str()) else {
+            return false;
+        };
+        pattern.is_match(relpath) && content.is_none_or(|content| Self::is_content_match(content, path))
+    }
+
+    fn is_content_match(content: &Regex, path: &Path) -> bool {
+        path.is_file()
+            && fs::read(path).is_ok_and(|bytes| content.is_match(&String::from_utf8_lossy(&bytes)))
     }
 }
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use serde::{
+    de::{self, Visitor},
+    Deserializer,
+};
+
+use crate::utils;
+
+/// Accepts either a number of seconds or a string such as `"500ms"`, `"30s"`, `"10m"`, `"1h"` or `"1d"`.
+pub fn deserialize_option<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct DurationVisitor;
+    impl<'de> Visitor<'de> for DurationVisitor {
+        type Value = Option<Duration>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(formatter, "duration")
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            u64::try_from(v)
+                .map(|secs| Some(Duration::from_secs(secs)))
+                .map_err(|_| de::Error::invalid_value(de::Unexpected::Signed(v), &self))
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            utils::parse_duration(v)
+                .map(Some)
+                .map_err(de::Error::custom)
+        }
+    }
+
+    deserializer.deserialize_any(DurationVisitor)
+}
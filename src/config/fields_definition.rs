@@ -1,28 +1,159 @@
-use std::collections::HashMap;
-use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use regex::Regex;
+use serde::{Deserialize, de::{self, Visitor, SeqAccess, MapAccess, value::{MapAccessDeserializer, SeqAccessDeserializer}}};
 
+use crate::store;
+use crate::table::{Align, Color};
+
+use super::duration;
 use super::generator::Generator;
 
 fn get_preset() -> Vec<(&'static str, Generator)> {
     vec![
         ("path", Generator::Template("${DEPOT_LOCAL_REL_PATH}".to_owned())),
         ("full-path", Generator::Template("${DEPOT_LOCAL_PATH}".to_owned())),
+        ("host", Generator::Template("$(path_segment DEPOT_LOCAL_REL_PATH 0)".to_owned())),
+        ("kind", Generator::Template("${DEPOT_PROJECT_KIND}".to_owned())),
     ]
 }
 
+/// A `list` field: either a bare generator or `{ generator = ..., cache = { ... }, align = 'right', ... }`.
+/// `$(field NAME)` in a template, or the `DEPOT_FIELD_NAME` variable in a command, refers to another field,
+/// which is then computed first.
+pub struct Field {
+    pub generator: Generator,
+    pub cache: Option<FieldCache>,
+    /// Names of the fields this one refers to, found when the config is loaded.
+    pub dependencies: Vec<String>,
+    pub display: FieldDisplay,
+}
+
+/// How `list --table` shows the field's column.
+#[derive(Default)]
+pub struct FieldDisplay {
+    pub align: Align,
+    pub max_width: Option<usize>,
+    pub color: Option<Color>,
+}
+impl From<Generator> for Field {
+    fn from(generator: Generator) -> Self {
+        Self {
+            generator,
+            cache: None,
+            dependencies: Vec::new(),
+            display: Default::default(),
+        }
+    }
+}
+
+/// Lets `list` reuse a computed value while the project directory and the `invalidate_on` files
+/// keep their modification times, and for at most `ttl` if given.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldCache {
+    #[serde(default, deserialize_with = "duration::deserialize_option")]
+    pub ttl: Option<Duration>,
+    /// Templates of paths, e.g. `${DEPOT_LOCAL_PATH}/.git/HEAD`.
+    #[serde(default)]
+    pub invalidate_on: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldTable {
+    generator: Generator,
+    cache: Option<FieldCache>,
+    #[serde(default)]
+    align: Align,
+    max_width: Option<usize>,
+    color: Option<Color>,
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct FieldVisitor;
+        impl<'de> Visitor<'de> for FieldVisitor {
+            type Value = Field;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "generator or field table")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Generator::Template(v.to_owned()).into())
+            }
+
+            fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                Ok(Generator::deserialize(SeqAccessDeserializer::new(seq))?.into())
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let table = FieldTable::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(Field {
+                    generator: table.generator,
+                    cache: table.cache,
+                    dependencies: Vec::new(),
+                    display: FieldDisplay {
+                        align: table.align,
+                        max_width: table.max_width,
+                        color: table.color,
+                    },
+                })
+            }
+        }
+
+        deserializer.deserialize_any(FieldVisitor)
+    }
+}
+
 pub struct FieldsDefinition {
-    fields: HashMap<String, Generator>,
+    fields: HashMap<String, Field>,
 }
 impl FieldsDefinition {
-    pub fn get(&self, name: &str) -> Option<&Generator> {
+    pub fn get(&self, name: &str) -> Option<&Field> {
         self.fields.get(name)
     }
+
+    /// Calls `visit` with the field and every field it refers to, transitively; each once, dependencies first.
+    pub fn walk<F: FnMut(&str, &Field)>(&self, name: &str, visit: &mut F) {
+        fn walk_from<F: FnMut(&str, &Field)>(
+            fields: &HashMap<String, Field>,
+            name: &str,
+            visited: &mut HashSet<String>,
+            visit: &mut F,
+        ) {
+            let Some(field) = fields.get(name) else {
+                return;
+            };
+            if !visited.insert(name.to_owned()) {
+                return;
+            }
+            for dependency in &field.dependencies {
+                walk_from(fields, dependency, visited, visit);
+            }
+            visit(name, field);
+        }
+        walk_from(&self.fields, name, &mut HashSet::new(), visit);
+    }
 }
 impl Default for FieldsDefinition {
     fn default() -> Self {
-        let mut fields: HashMap<String, Generator> = HashMap::new();
+        let mut fields: HashMap<String, Field> = HashMap::new();
         for def in get_preset() {
-            fields.insert(def.0.to_owned(), def.1);
+            fields.insert(def.0.to_owned(), def.1.into());
         }
         Self { fields: fields }
     }
@@ -32,16 +163,158 @@ impl<'de> Deserialize<'de> for FieldsDefinition {
     where
         D: serde::Deserializer<'de>
     {
-        let mut fields: HashMap<String, Generator> = Deserialize::deserialize(deserializer)?;
+        let mut fields: HashMap<String, Field> = Deserialize::deserialize(deserializer)?;
 
         for def in get_preset() {
             if ! fields.contains_key(def.0) {
-                fields.insert(def.0.to_owned(), def.1);
+                fields.insert(def.0.to_owned(), def.1.into());
             }
         }
 
+        resolve_dependencies(&mut fields).map_err(de::Error::custom)?;
         Ok(Self {
             fields: fields,
         })
     }
 }
+
+/// Fills in `Field::dependencies` and rejects references to unknown fields and reference cycles.
+fn resolve_dependencies(fields: &mut HashMap<String, Field>) -> Result<(), String> {
+    let function = Regex::new(r"\$\(field ([^ )]+)\)").expect("valid regex");
+    let variable = Regex::new(r"\bDEPOT_FIELD_([A-Za-z0-9_]+)").expect("valid regex");
+    let names_by_var: HashMap<String, String> = fields
+        .keys()
+        .map(|name| (store::field_var_name(name), name.clone()))
+        .collect();
+
+    let mut names: Vec<String> = fields.keys().cloned().collect();
+    names.sort();
+    let mut resolved = Vec::new();
+    for name in &names {
+        let field = &fields[name];
+        let sources: &[String] = match &field.generator {
+            Generator::String(_) => &[],
+            Generator::Template(template) => std::slice::from_ref(template),
+            Generator::Shell(commands) => commands,
+            Generator::Exec(args) => args,
+        };
+        let mut dependencies: Vec<String> = Vec::new();
+        for source in sources {
+            for captures in function.captures_iter(source) {
+                let dependency = captures[1].to_owned();
+                if !fields.contains_key(&dependency) {
+                    return Err(format!("field '{}' refers to unknown field '{}'", name, dependency));
+                }
+                dependencies.push(dependency);
+            }
+            for captures in variable.captures_iter(source) {
+                let var = format!("DEPOT_FIELD_{}", &captures[1]);
+                let dependency = names_by_var
+                    .get(&var)
+                    .ok_or_else(|| format!("field '{}' refers to unknown field variable '{}'", name, var))?;
+                dependencies.push(dependency.clone());
+            }
+        }
+        dependencies.sort();
+        dependencies.dedup();
+        resolved.push((name, dependencies));
+    }
+    for (name, dependencies) in resolved {
+        if let Some(field) = fields.get_mut(name) {
+            field.dependencies = dependencies;
+        }
+    }
+
+    // depth-first search; `path` holds the fields being resolved
+    fn visit(fields: &HashMap<String, Field>, name: &str, path: &mut Vec<String>, done: &mut HashSet<String>) -> Result<(), String> {
+        if done.contains(name) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|entry| entry == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name.to_owned());
+            return Err(format!("field reference cycle: {}", cycle.join(" -> ")));
+        }
+        path.push(name.to_owned());
+        for dependency in &fields[name].dependencies {
+            visit(fields, dependency, path, done)?;
+        }
+        path.pop();
+        done.insert(name.to_owned());
+        Ok(())
+    }
+    let mut done = HashSet::new();
+    for name in &names {
+        visit(fields, name, &mut Vec::new(), &mut done)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(source: &str) -> Result<FieldsDefinition, String> {
+        toml::from_str(source).map_err(|err| err.to_string())
+    }
+
+    fn load_err(source: &str) -> String {
+        match load(source) {
+            Ok(_) => panic!("expected an error for {}", source),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn dependencies_are_resolved() {
+        let fields = load(
+            "display = '$(field path) ($(field git-branch))'\n\
+             git-branch = [ 'shell', 'echo main' ]\n\
+             shout = [ 'shell', 'echo \"$DEPOT_FIELD_DISPLAY\"' ]\n",
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(fields.get("display").unwrap().dependencies, vec!["git-branch", "path"]);
+        assert_eq!(fields.get("shout").unwrap().dependencies, vec!["display"]);
+        assert!(fields.get("git-branch").unwrap().dependencies.is_empty());
+    }
+
+    #[test]
+    fn walk_reaches_dependencies_first() {
+        let fields = load(
+            "disp = '$(field cnt) $(field path)'\n\
+             cnt = { generator = [ 'shell', 'echo 1' ], cache = { ttl = '1h' } }\n\
+             twice = '$(field disp) $(field cnt)'\n",
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+        let mut visited = Vec::new();
+        let mut uses_cache = false;
+        fields.walk("twice", &mut |name, field| {
+            visited.push(name.to_owned());
+            uses_cache |= field.cache.is_some();
+        });
+        assert_eq!(visited, vec!["cnt", "path", "disp", "twice"]);
+        assert!(uses_cache);
+
+        let mut visited = Vec::new();
+        fields.walk("missing", &mut |name, _| visited.push(name.to_owned()));
+        assert!(visited.is_empty());
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let err = load_err("a = '$(field b)'\nb = [ 'shell', 'echo $DEPOT_FIELD_C' ]\nc = '$(field a)'\n");
+        assert!(err.contains("field reference cycle: a -> b -> c -> a"), "{}", err);
+
+        let err = load_err("a = '$(field a)'\n");
+        assert!(err.contains("field reference cycle: a -> a"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_references_are_rejected() {
+        let err = load_err("a = '$(field missing)'\n");
+        assert!(err.contains("refers to unknown field 'missing'"), "{}", err);
+
+        let err = load_err("a = [ 'shell', 'echo $DEPOT_FIELD_MISSING' ]\n");
+        assert!(err.contains("unknown field variable 'DEPOT_FIELD_MISSING'"), "{}", err);
+    }
+}
@@ -1,12 +1,15 @@
 use std::fmt::Write;
 
-use crate::{store::Store, utils::{GenericResult, CommandGenerator, trim_end}, template};
+use std::{os::unix::process::ExitStatusExt, process::Command};
+
+use crate::{store::Store, utils::{GenericResult, CommandGenerator, trim_end}, template, config::shell, error::{CustomError, ProcessError}, exit_code, runner::{self, Capture, Outcome}};
 use serde::{Deserialize, de::{Visitor, SeqAccess, self, Unexpected}};
 
 pub enum Generator {
     String(String),
     Template(String),
     Shell(Vec<String>),
+    Exec(Vec<String>),
 }
 impl<'de> Deserialize<'de> for Generator {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -64,6 +67,16 @@ impl<'de> Deserialize<'de> for Generator {
                         }
                         Ok(Self::Value::Shell(commands))
                     },
+                    "exec" => {
+                        let mut args = Vec::<String>::new();
+                        while let Some(arg) = seq.next_element::<String>()? {
+                            args.push(arg);
+                        }
+                        if args.is_empty() {
+                            return Err(de::Error::invalid_length(1, &self));
+                        }
+                        Ok(Self::Value::Exec(args))
+                    },
                     _ => Err(de::Error::invalid_value(Unexpected::Str(&method), &self)),
                 }
             }
@@ -81,14 +94,31 @@ impl Generator {
         Ok(template::expand_template(store, template))
     }
 
+    fn run<Tc: CommandGenerator>(cmdgen: &Tc, command: &mut Command, description: &str) -> GenericResult<(i32, String)> {
+        let options = cmdgen.options();
+        if let Some(cwd) = cmdgen.current_dir() {
+            command.current_dir(cwd);
+        }
+        match runner::run(command, &options, Capture::All)? {
+            Outcome::Exited(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                if let Some(signal) = output.status.signal() {
+                    let message = format!("killed by {}: {}\n{}", exit_code::signal_name(signal), description, stderr);
+                    return Err(Box::new(ProcessError::new(message.trim_end())));
+                }
+                eprint!("{}", stderr);
+                Ok((exit_code::from_status(&output.status), options.decode(output.stdout)?))
+            },
+            Outcome::TimedOut(timeout) => Err(Box::new(ProcessError::new(&format!("timed out after {:?}: {}", timeout, description)))),
+            Outcome::Interrupted => Err(Box::new(ProcessError::new(&format!("interrupted: {}", description)))),
+        }
+    }
+
     fn execute_shell<Tc: CommandGenerator, Ts: Store>(cmdgen: &Tc, store: &Ts, commands: &Vec<String>) -> GenericResult<String> {
         let mut buffer = String::new();
         for command in commands {
-            let output = cmdgen.generate(store)
-                .arg(command)
-                .output()?;
-            let code = output.status.code().unwrap_or(0);
-            buffer.write_str(std::str::from_utf8(&output.stdout)?)?;
+            let (code, stdout) = Self::run(cmdgen, cmdgen.generate(store).arg(command), command)?;
+            buffer.write_str(&stdout)?;
 
             if code != 0 {
                 break;
@@ -97,11 +127,21 @@ impl Generator {
         Ok(trim_end(buffer))
     }
 
+    fn execute_exec<Tc: CommandGenerator, Ts: Store>(cmdgen: &Tc, store: &Ts, args: &[String]) -> GenericResult<String> {
+        let description = args.join(" ");
+        let (code, stdout) = Self::run(cmdgen, &mut shell::generate_exec(store, args)?, &description)?;
+        if code != 0 {
+            return Err(Box::new(ProcessError::new(&format!("exit {}: {}", code, description))));
+        }
+        Ok(trim_end(stdout))
+    }
+
     pub fn expand_without_shell<T: Store>(&self, store: &T) -> GenericResult<String> {
         match self {
             Generator::String(value) => Self::expand_string(value),
             Generator::Template(template) => Self::expand_template(store, template),
-            Generator::Shell(_) => panic!("call expand_without_shell with shell"),
+            Generator::Shell(_) => Err(Box::new(CustomError::new("shell generator cannot be expanded here"))),
+            Generator::Exec(_) => Err(Box::new(CustomError::new("exec generator cannot be expanded here"))),
         }
     }
 
@@ -110,6 +150,7 @@ impl Generator {
             Generator::String(value) => Self::expand_string(value),
             Generator::Template(template) => Self::expand_template(store, template),
             Generator::Shell(commands) => Self::execute_shell(cmdgen, store, commands),
+            Generator::Exec(args) => Self::execute_exec(cmdgen, store, args),
         }
     }
 }
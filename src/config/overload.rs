@@ -1,32 +1,200 @@
 use std::collections::HashMap;
+use std::path::Path;
 
-use serde::Deserialize;
+use globset::{GlobSet, GlobSetBuilder};
+use serde::{de, Deserialize};
+use url::Url;
 
-use crate::utils::{GenericResult, Compilable};
+use crate::error::CustomError;
+use crate::store::Store;
+use crate::template;
+use crate::utils::{GenericResult, Compilable, DirectoryMatcher, StringMatcher};
 
-use super::pattern::Pattern;
+use super::directory_condition::{CompiledDirectoryCondition, DirectoryCondition};
+use super::pattern::{self, Pattern};
 
-#[derive(Deserialize)]
-#[serde(transparent)]
 pub struct OverloadsContainer {
     overloads: Vec<Overload>,
+    matcher: OverloadMatcher,
+    conditions: Vec<Option<CompiledDirectoryCondition>>,
 }
 impl OverloadsContainer {
-    pub fn find_overload_name(&self, param: &str) -> GenericResult<Option<&str>> {
-        for overload in &self.overloads {
-            for pattern in &overload.patterns {
-                let matcher = pattern.compile()?;
-                if matcher.is_match(param) {
-                    return Ok(Some(&overload.name));
+    pub fn new(mut overloads: Vec<Overload>) -> GenericResult<Self> {
+        let chains = Self::resolve_chains(&overloads)?;
+        let mut inherited_vars = Vec::new();
+        for chain in &chains {
+            let mut vars = HashMap::new();
+            for name in chain.iter().rev() {
+                if let Some(ancestor) = overloads.iter().find(|o| &o.name == name) {
+                    vars.extend(ancestor.vars.clone());
+                }
+            }
+            inherited_vars.push(vars);
+        }
+        for ((overload, chain), vars) in overloads.iter_mut().zip(chains).zip(inherited_vars) {
+            overload.chain = chain;
+            overload.vars = vars;
+        }
+        let matcher = OverloadMatcher::compile(&overloads)?;
+        let mut conditions = Vec::new();
+        for overload in &overloads {
+            conditions.push(overload.condition.as_ref().map(|c| c.compile()).transpose()?);
+        }
+        Ok(Self {
+            overloads,
+            matcher,
+            conditions,
+        })
+    }
+
+    fn resolve_chains(overloads: &[Overload]) -> GenericResult<Vec<Vec<String>>> {
+        let mut chains = Vec::new();
+        for overload in overloads {
+            let mut chain = vec![overload.name.clone()];
+            let mut current = overload;
+            while let Some(parent_name) = &current.extends {
+                if chain.contains(parent_name) {
+                    chain.push(parent_name.clone());
+                    return Err(Box::new(CustomError::new(&format!(
+                        "overload inheritance cycle: {}",
+                        chain.join(" -> ")
+                    ))));
                 }
+                current = overloads.iter().find(|o| &o.name == parent_name).ok_or_else(|| {
+                    CustomError::new(&format!(
+                        "overload '{}' extends unknown overload '{}'",
+                        current.name, parent_name
+                    ))
+                })?;
+                chain.push(parent_name.clone());
             }
+            chains.push(chain);
         }
-        return Ok(None);
+        Ok(chains)
+    }
+
+    /// Returns every overload matching the address, most preferred first.
+    pub fn find_overloads(&self, address: &str, url: &Url) -> Vec<OverloadMatch<'_>> {
+        let mut matched: Vec<(usize, usize)> = Vec::new();
+        for (overload_index, pattern_index) in self.matcher.matches(address, url) {
+            if let Some(entry) = matched.iter_mut().find(|(o, _)| *o == overload_index) {
+                entry.1 = entry.1.min(pattern_index);
+            } else {
+                matched.push((overload_index, pattern_index));
+            }
+        }
+        matched.sort_by_key(|&(overload_index, _)| {
+            (-self.overloads[overload_index].priority, overload_index)
+        });
+        matched
+            .into_iter()
+            .map(|(overload_index, pattern_index)| {
+                let overload = &self.overloads[overload_index];
+                OverloadMatch {
+                    overload,
+                    pattern: &overload.patterns[pattern_index],
+                }
+            })
+            .collect()
+    }
+
+    pub fn find_overload(&self, address: &str, url: &Url) -> Option<&Overload> {
+        let matches = self.find_overloads(address, url);
+        let first = matches.first()?;
+        for other in matches[1..].iter().take_while(|m| m.overload.priority == first.overload.priority) {
+            eprintln!(
+                "warning: overloads '{}' and '{}' both match '{}' with priority {}, using '{}'",
+                first.overload.name, other.overload.name, address, first.overload.priority, first.overload.name
+            );
+        }
+        Some(first.overload)
+    }
+
+    /// Returns the overload whose `condition` recognizes the project directory, for projects found on disk
+    /// rather than given by address.
+    pub fn find_overload_for_directory(&self, root: &Path, path: &Path) -> Option<&Overload> {
+        let mut indices: Vec<usize> = (0..self.overloads.len()).collect();
+        indices.sort_by_key(|&index| (-self.overloads[index].priority, index));
+        indices
+            .into_iter()
+            .find(|&index| {
+                self.conditions[index]
+                    .as_ref()
+                    .is_some_and(|condition| condition.is_match(root, path))
+            })
+            .map(|index| &self.overloads[index])
+    }
+
+    /// Compiles the `condition` of every overload that has one, for walks looking for projects of any overload.
+    pub fn compile_conditions(&self) -> GenericResult<Vec<CompiledDirectoryCondition>> {
+        self.overloads
+            .iter()
+            .filter_map(|overload| overload.condition.as_ref())
+            .map(|condition| condition.compile())
+            .collect()
     }
 }
 impl Default for OverloadsContainer {
     fn default() -> Self {
-        Self { overloads: Default::default() }
+        Self::new(Vec::new()).expect("empty overload table")
+    }
+}
+impl<'de> Deserialize<'de> for OverloadsContainer {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let overloads: Vec<Overload> = Deserialize::deserialize(deserializer)?;
+        Self::new(overloads).map_err(de::Error::custom)
+    }
+}
+
+pub struct OverloadMatch<'a> {
+    pub overload: &'a Overload,
+    pub pattern: &'a Pattern,
+}
+
+/// All glob patterns of the overload table share one `GlobSet`; other patterns are compiled individually.
+struct OverloadMatcher {
+    globs: GlobSet,
+    glob_owners: Vec<(usize, usize)>,
+    others: Vec<(usize, usize, Box<dyn StringMatcher>)>,
+}
+impl OverloadMatcher {
+    fn compile(overloads: &[Overload]) -> GenericResult<Self> {
+        let mut builder = GlobSetBuilder::new();
+        let mut glob_owners = Vec::new();
+        let mut others = Vec::new();
+        for (overload_index, overload) in overloads.iter().enumerate() {
+            for (pattern_index, pattern) in overload.patterns.iter().enumerate() {
+                if let Pattern::Glob(glob) = pattern {
+                    builder.add(pattern::build_glob(glob)?);
+                    glob_owners.push((overload_index, pattern_index));
+                } else {
+                    others.push((overload_index, pattern_index, pattern.compile()?));
+                }
+            }
+        }
+        Ok(Self {
+            globs: builder.build()?,
+            glob_owners,
+            others,
+        })
+    }
+
+    fn matches(&self, address: &str, url: &Url) -> Vec<(usize, usize)> {
+        let mut result: Vec<(usize, usize)> = self
+            .globs
+            .matches(address)
+            .into_iter()
+            .map(|index| self.glob_owners[index])
+            .collect();
+        for (overload_index, pattern_index, matcher) in &self.others {
+            if matcher.is_match_address(address, url) {
+                result.push((*overload_index, *pattern_index));
+            }
+        }
+        result
     }
 }
 
@@ -34,6 +202,26 @@ impl Default for OverloadsContainer {
 pub struct Overload {
     pub name: String,
     pub patterns: Vec<Pattern>,
+    #[serde(default)]
+    pub priority: i32,
+    pub extends: Option<String>,
+    /// Recognizes projects of this overload on disk, e.g. `[ { mode = 'parent', pattern = '**/.git' } ]`.
+    pub condition: Option<DirectoryCondition>,
+    /// Includes the vars inherited through `extends` once loaded.
+    #[serde(default)]
+    pub vars: HashMap<String, String>,
+    /// This overload followed by its ancestors, resolved at load time.
+    #[serde(skip)]
+    pub chain: Vec<String>,
+}
+impl Overload {
+    pub fn export<T: Store>(&self, store: &mut T) {
+        store.set_overload_name(&self.name);
+        for (name, value) in &self.vars {
+            let value = template::expand_template(store, value);
+            store.set_overload_var(name, value);
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -43,10 +231,27 @@ pub struct Overloadable<T> {
     #[serde(default)]
     overloads: HashMap<String, T>,
 }
+impl<T: Default> Default for Overloadable<T> {
+    fn default() -> Self {
+        Self {
+            params: Default::default(),
+            overloads: HashMap::new(),
+        }
+    }
+}
 impl<T> Overloadable<T> {
-    pub fn get_params(&self, name: Option<&str>) -> &T {
-        if let Some(name) = name {
-            self.overloads.get(name).unwrap_or(&self.params)
+    /// Returns the name in the overload's chain that has its own params, or `None` for the defaults.
+    pub fn find_params_name<'a>(&self, overload: Option<&'a Overload>) -> Option<&'a str> {
+        overload?
+            .chain
+            .iter()
+            .find(|name| self.overloads.contains_key(*name))
+            .map(|name| name.as_str())
+    }
+
+    pub fn get_params(&self, overload: Option<&Overload>) -> &T {
+        if let Some(name) = self.find_params_name(overload) {
+            &self.overloads[name]
         } else {
             &self.params
         }
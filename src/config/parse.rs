@@ -1,3 +1,6 @@
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
 use regex::Regex;
 use serde::Deserialize;
 use url::Url;
@@ -11,8 +14,7 @@ pub struct Parse {
 
 impl Parse {
     pub fn parse_url(&self, uri: &str) -> GenericResult<Url> {
-        let scp_regex = Regex::new(r"^(\w+)@([\w\.]+):(.+)$")?;
-        let converted_url_str = scp_regex.replace(uri, "ssh://$1@$2/$3");
+        let converted_url_str = convert_scp_address(uri);
         let base_url_str = format!("{}://{}/", &self.default.scheme, &self.default.host);
         let base_url = Url::parse(&base_url_str)?;
         let remote_url = Url::options().base_url(Some(&base_url)).parse(&converted_url_str)?;
@@ -21,6 +23,12 @@ impl Parse {
     }
 }
 
+pub fn convert_scp_address(uri: &str) -> Cow<'_, str> {
+    static SCP_REGEX: OnceLock<Regex> = OnceLock::new();
+    let scp_regex = SCP_REGEX.get_or_init(|| Regex::new(r"^(\w+)@([\w\.]+):(.+)$").expect("valid scp regex"));
+    scp_regex.replace(uri, "ssh://$1@$2/$3")
+}
+
 #[derive(Deserialize)]
 pub struct ParseDefault {
     #[serde(default = "ParseDefault::default_scheme")]
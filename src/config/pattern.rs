@@ -1,12 +1,68 @@
-use globset::{GlobBuilder, GlobMatcher as GlobSetMatcher};
-use serde::{Deserialize, de::{Visitor, self, SeqAccess, Unexpected}};
+use std::fmt::{self, Display, Formatter};
+
+use globset::{Glob, GlobBuilder, GlobMatcher as GlobSetMatcher};
+use regex::Regex;
+use serde::{Deserialize, de::{Visitor, self, SeqAccess, MapAccess, Unexpected, value::MapAccessDeserializer}};
+use url::Url;
 
 use crate::utils::{GenericResult, StringMatcher, Compilable};
 
+use super::parse;
+
 pub enum Pattern {
     Glob(String),
     StartsWith(String),
+    Regex(String),
+    Url(UrlPattern),
+    Not(Box<Pattern>),
+    Any(Vec<Pattern>),
+    All(Vec<Pattern>),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UrlPattern {
+    pub scheme: Option<String>,
+    pub user: Option<String>,
+    pub host: Option<String>,
+    pub path: Option<String>,
+}
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let write_list = |f: &mut Formatter<'_>, method: &str, patterns: &[Pattern]| {
+            write!(f, "['{}'", method)?;
+            for pattern in patterns {
+                write!(f, ", {}", pattern)?;
+            }
+            write!(f, "]")
+        };
+        match self {
+            Pattern::Glob(pattern) => write!(f, "'{}'", pattern),
+            Pattern::StartsWith(pattern) => write!(f, "['starts-with', '{}']", pattern),
+            Pattern::Regex(pattern) => write!(f, "['regex', '{}']", pattern),
+            Pattern::Url(pattern) => write!(f, "{}", pattern),
+            Pattern::Not(pattern) => write!(f, "['not', {}]", pattern),
+            Pattern::Any(patterns) => write_list(f, "any", patterns),
+            Pattern::All(patterns) => write_list(f, "all", patterns),
+        }
+    }
 }
+impl Display for UrlPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let components = [
+            ("scheme", &self.scheme),
+            ("user", &self.user),
+            ("host", &self.host),
+            ("path", &self.path),
+        ];
+        let fields: Vec<String> = components
+            .iter()
+            .filter_map(|(name, value)| value.as_ref().map(|value| format!("{} = '{}'", name, value)))
+            .collect();
+        write!(f, "{{ {} }}", fields.join(", "))
+    }
+}
+
 impl<'de> Deserialize<'de> for Pattern {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -25,6 +81,13 @@ impl<'de> Deserialize<'de> for Pattern {
             {
                 Ok(Self::Value::Glob(v.to_owned()))
             }
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let pattern = UrlPattern::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(Self::Value::Url(pattern))
+            }
             fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
             where
                 V: SeqAccess<'de>,
@@ -45,6 +108,38 @@ impl<'de> Deserialize<'de> for Pattern {
                             .ok_or_else(|| de::Error::invalid_length(1, &self))?;
                         Ok(Self::Value::StartsWith(pattern))
                     }
+                    "regex" => {
+                        let pattern = seq
+                            .next_element::<String>()?
+                            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                        Ok(Self::Value::Regex(pattern))
+                    }
+                    "url" => {
+                        let pattern = seq
+                            .next_element::<UrlPattern>()?
+                            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                        Ok(Self::Value::Url(pattern))
+                    }
+                    "not" => {
+                        let pattern = seq
+                            .next_element::<Pattern>()?
+                            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                        Ok(Self::Value::Not(Box::new(pattern)))
+                    }
+                    "any" | "all" => {
+                        let mut patterns = Vec::<Pattern>::new();
+                        while let Some(pattern) = seq.next_element::<Pattern>()? {
+                            patterns.push(pattern);
+                        }
+                        if patterns.is_empty() {
+                            return Err(de::Error::invalid_length(1, &self));
+                        }
+                        if method == "any" {
+                            Ok(Self::Value::Any(patterns))
+                        } else {
+                            Ok(Self::Value::All(patterns))
+                        }
+                    }
                     _ => Err(de::Error::invalid_value(Unexpected::Str(&method), &self)),
                 }
             }
@@ -54,22 +149,65 @@ impl<'de> Deserialize<'de> for Pattern {
     }
 }
 
+pub fn build_glob(pattern: &str) -> GenericResult<Glob> {
+    let glob = GlobBuilder::new(pattern)
+        .literal_separator(true).build()
+        .or_else(|err| { eprintln!("{}", err); Err(err) })?;
+    Ok(glob)
+}
+
+fn compile_glob(pattern: &str) -> GenericResult<GlobSetMatcher> {
+    Ok(build_glob(pattern)?.compile_matcher())
+}
+
+fn compile_all(patterns: &[Pattern]) -> GenericResult<Vec<Box<dyn StringMatcher>>> {
+    let mut matchers = Vec::<Box<dyn StringMatcher>>::new();
+    for pattern in patterns {
+        matchers.push(pattern.compile()?);
+    }
+    Ok(matchers)
+}
+
 impl Compilable<Box<dyn StringMatcher>> for Pattern {
     fn compile(&self) -> GenericResult<Box<dyn StringMatcher>> {
         match self {
             Pattern::Glob(pattern) => {
-                let glob = GlobBuilder::new(pattern)
-                    .literal_separator(true).build()
-                    .or_else(|err| { eprintln!("{}", err); Err(err) })?;
-                Ok(Box::new(GlobMatcher::new(glob.compile_matcher())))
+                Ok(Box::new(GlobMatcher::new(compile_glob(pattern)?)))
             }
             Pattern::StartsWith(pattern) => {
                 Ok(Box::new(StartsWithMatcher::new(pattern.to_owned())))
             }
+            Pattern::Regex(pattern) => {
+                Ok(Box::new(RegexMatcher::new(Regex::new(pattern)?)))
+            }
+            Pattern::Url(pattern) => {
+                Ok(Box::new(pattern.compile()?))
+            }
+            Pattern::Not(pattern) => {
+                Ok(Box::new(NotMatcher::new(pattern.compile()?)))
+            }
+            Pattern::Any(patterns) => {
+                Ok(Box::new(AnyMatcher::new(compile_all(patterns)?)))
+            }
+            Pattern::All(patterns) => {
+                Ok(Box::new(AllMatcher::new(compile_all(patterns)?)))
+            }
         }
     }
 }
 
+impl Compilable<UrlMatcher> for UrlPattern {
+    fn compile(&self) -> GenericResult<UrlMatcher> {
+        let compile_component = |component: &Option<String>| component.as_deref().map(compile_glob).transpose();
+        Ok(UrlMatcher {
+            scheme: compile_component(&self.scheme)?,
+            user: compile_component(&self.user)?,
+            host: compile_component(&self.host)?,
+            path: compile_component(&self.path)?,
+        })
+    }
+}
+
 pub struct GlobMatcher {
     glob_matcher: GlobSetMatcher,
 }
@@ -102,3 +240,106 @@ impl StringMatcher for StartsWithMatcher {
     }
 }
 
+pub struct RegexMatcher {
+    regex: Regex,
+}
+impl RegexMatcher {
+    fn new(regex: Regex) -> Self {
+        Self {
+            regex,
+        }
+    }
+}
+impl StringMatcher for RegexMatcher {
+    fn is_match(&self, str: &str) -> bool {
+        self.regex.is_match(str)
+    }
+}
+
+pub struct UrlMatcher {
+    scheme: Option<GlobSetMatcher>,
+    user: Option<GlobSetMatcher>,
+    host: Option<GlobSetMatcher>,
+    path: Option<GlobSetMatcher>,
+}
+impl UrlMatcher {
+    fn is_component_match(matcher: &Option<GlobSetMatcher>, value: &str) -> bool {
+        matcher.as_ref().is_none_or(|matcher| matcher.is_match(value))
+    }
+}
+impl StringMatcher for UrlMatcher {
+    fn is_match(&self, str: &str) -> bool {
+        if let Ok(url) = Url::parse(&parse::convert_scp_address(str)) {
+            self.is_match_address(str, &url)
+        } else {
+            false
+        }
+    }
+
+    fn is_match_address(&self, _: &str, url: &Url) -> bool {
+        Self::is_component_match(&self.scheme, url.scheme())
+            && Self::is_component_match(&self.user, url.username())
+            && Self::is_component_match(&self.host, url.host_str().unwrap_or(""))
+            && Self::is_component_match(&self.path, url.path())
+    }
+}
+
+pub struct NotMatcher {
+    matcher: Box<dyn StringMatcher>,
+}
+impl NotMatcher {
+    fn new(matcher: Box<dyn StringMatcher>) -> Self {
+        Self {
+            matcher,
+        }
+    }
+}
+impl StringMatcher for NotMatcher {
+    fn is_match(&self, str: &str) -> bool {
+        !self.matcher.is_match(str)
+    }
+
+    fn is_match_address(&self, address: &str, url: &Url) -> bool {
+        !self.matcher.is_match_address(address, url)
+    }
+}
+
+pub struct AnyMatcher {
+    matchers: Vec<Box<dyn StringMatcher>>,
+}
+impl AnyMatcher {
+    fn new(matchers: Vec<Box<dyn StringMatcher>>) -> Self {
+        Self {
+            matchers,
+        }
+    }
+}
+impl StringMatcher for AnyMatcher {
+    fn is_match(&self, str: &str) -> bool {
+        self.matchers.iter().any(|matcher| matcher.is_match(str))
+    }
+
+    fn is_match_address(&self, address: &str, url: &Url) -> bool {
+        self.matchers.iter().any(|matcher| matcher.is_match_address(address, url))
+    }
+}
+
+pub struct AllMatcher {
+    matchers: Vec<Box<dyn StringMatcher>>,
+}
+impl AllMatcher {
+    fn new(matchers: Vec<Box<dyn StringMatcher>>) -> Self {
+        Self {
+            matchers,
+        }
+    }
+}
+impl StringMatcher for AllMatcher {
+    fn is_match(&self, str: &str) -> bool {
+        self.matchers.iter().all(|matcher| matcher.is_match(str))
+    }
+
+    fn is_match_address(&self, address: &str, url: &Url) -> bool {
+        self.matchers.iter().all(|matcher| matcher.is_match_address(address, url))
+    }
+}
@@ -0,0 +1,92 @@
+use toml::{value::Table, Value};
+
+use crate::{error::CustomError, utils::GenericResult};
+
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    source: &'static str,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "git",
+        description: "Git repositories (git://, git+ssh://, https://, scp-style *.git)",
+        source: include_str!("../../files/presets/git.toml"),
+    },
+    Preset {
+        name: "hg",
+        description: "Mercurial repositories (hg://, hg+https://, hg+ssh://)",
+        source: include_str!("../../files/presets/hg.toml"),
+    },
+    Preset {
+        name: "svn",
+        description: "Subversion working copies (svn://, svn+ssh://, svn+https://)",
+        source: include_str!("../../files/presets/svn.toml"),
+    },
+    Preset {
+        name: "fossil",
+        description: "Fossil repositories (fossil+https://, fossil+ssh://)",
+        source: include_str!("../../files/presets/fossil.toml"),
+    },
+    Preset {
+        name: "pijul",
+        description: "Pijul repositories (pijul+https://, pijul+ssh://)",
+        source: include_str!("../../files/presets/pijul.toml"),
+    },
+    Preset {
+        name: "tarball",
+        description: "Archives extracted with curl and tar/unzip (*.tar.gz, *.tgz, *.zip, ...)",
+        source: include_str!("../../files/presets/tarball.toml"),
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
+
+/// Fills in everything the enabled presets define and the user config does not.
+/// Preset overloads are appended after the user's own; overloads with a name the user already defined are merged.
+pub fn apply(config: &mut Value, names: &[String]) -> GenericResult<()> {
+    for name in names {
+        let preset = find(name)
+            .ok_or_else(|| CustomError::new(&format!("unknown preset: {}", name)))?;
+        let preset_value: Value = toml::from_str(preset.source)?;
+        if let (Value::Table(config), Value::Table(preset_table)) = (&mut *config, preset_value) {
+            merge_table(config, preset_table, true);
+        }
+    }
+    Ok(())
+}
+
+fn merge_table(target: &mut Table, source: Table, is_root: bool) {
+    for (key, value) in source {
+        match (target.get_mut(&key), value) {
+            (None, value) => {
+                target.insert(key, value);
+            }
+            (Some(Value::Table(target)), Value::Table(source)) => merge_table(target, source, false),
+            (Some(Value::Array(target)), Value::Array(source)) if is_root && key == "overloads" => {
+                merge_overloads(target, source)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// An overload the user already defined is completed with the preset's keys it does not set.
+fn merge_overloads(target: &mut Vec<Value>, source: Vec<Value>) {
+    let name_of = |overload: &Value| overload.get("name").and_then(|name| name.as_str()).map(|name| name.to_owned());
+    for overload in source {
+        let name = name_of(&overload);
+        match target.iter_mut().find(|existing| name_of(existing) == name) {
+            Some(Value::Table(existing)) => {
+                if let Value::Table(overload) = overload {
+                    merge_table(existing, overload, false);
+                }
+            }
+            Some(_) => {}
+            None => target.push(overload),
+        }
+    }
+}
@@ -2,7 +2,7 @@ use serde::Deserialize;
 
 use crate::{utils::{GenericResult, CommandGenerator}, store::Store};
 
-use super::{generator::Generator, overload::Overloadable};
+use super::{generator::Generator, overload::{Overload, Overloadable}};
 
 #[derive(Deserialize)]
 pub struct ResolveParams {
@@ -17,8 +17,8 @@ impl Default for ResolveParams {
 
 pub type Resolve = Overloadable<ResolveParams>;
 impl Resolve {
-    pub fn expand_path<Tc: CommandGenerator, Ts: Store>(&self, cmdgen: &Tc, store: &Ts, overload_name: Option<&str>) -> GenericResult<String> {
-        let params = self.get_params(overload_name);
+    pub fn expand_path<Tc: CommandGenerator, Ts: Store>(&self, cmdgen: &Tc, store: &Ts, overload: Option<&Overload>) -> GenericResult<String> {
+        let params = self.get_params(overload);
         params.path.expand(cmdgen, store)
     }
 }
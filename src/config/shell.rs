@@ -1,28 +1,70 @@
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer};
 
 use crate::{
+    error::CustomError,
+    runner::ProcessOptions,
     store::Store,
+    template,
     utils::{CommandGenerator, GenericResult},
 };
 
-use super::generator::Generator;
+use super::{duration, generator::Generator};
 
 #[derive(Deserialize)]
 pub struct Shell {
+    #[serde(deserialize_with = "deserialize_path")]
     pub path: Generator,
+    #[serde(deserialize_with = "deserialize_args")]
     pub args: Vec<Generator>,
+    #[serde(default, deserialize_with = "duration::deserialize_option")]
+    pub timeout: Option<Duration>,
+    #[serde(default)]
+    pub lossy_utf8: bool,
 }
 impl Default for Shell {
     fn default() -> Self {
         Self {
             path: Generator::String("/bin/sh".to_owned()),
             args: vec![Generator::String("-c".to_owned())],
+            timeout: None,
+            lossy_utf8: false,
         }
     }
 }
 
+/// The shell is compiled before anything can run, so its path and arguments cannot be `shell` or `exec` generators.
+fn check_generator<E: de::Error>(generator: &Generator) -> Result<(), E> {
+    match generator {
+        Generator::String(_) | Generator::Template(_) => Ok(()),
+        Generator::Shell(_) => Err(E::custom("shell generator is not allowed in the shell settings")),
+        Generator::Exec(_) => Err(E::custom("exec generator is not allowed in the shell settings")),
+    }
+}
+
+fn deserialize_path<'de, D>(deserializer: D) -> Result<Generator, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let path = Generator::deserialize(deserializer)?;
+    check_generator(&path)?;
+    Ok(path)
+}
+
+fn deserialize_args<'de, D>(deserializer: D) -> Result<Vec<Generator>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let args = Vec::<Generator>::deserialize(deserializer)?;
+    for arg in &args {
+        check_generator(arg)?;
+    }
+    Ok(args)
+}
+
 impl Shell {
     pub fn compile<T: Store>(&self, store: &T) -> GenericResult<CompiledShell> {
         let path = self.path.expand_without_shell(store)?;
@@ -34,13 +76,22 @@ impl Shell {
         Ok(CompiledShell {
             path: path,
             args: args,
+            options: ProcessOptions {
+                timeout: self.timeout,
+                lossy_utf8: self.lossy_utf8,
+                ..Default::default()
+            },
+            cwd: None,
         })
     }
 }
 
+#[derive(Clone)]
 pub struct CompiledShell {
     pub path: String,
     pub args: Vec<String>,
+    pub options: ProcessOptions,
+    pub cwd: Option<PathBuf>,
 }
 impl CommandGenerator for CompiledShell {
     fn generate<T: Store>(&self, store: &T) -> Command {
@@ -49,4 +100,24 @@ impl CommandGenerator for CompiledShell {
         command.args(&self.args);
         command
     }
+
+    fn options(&self) -> ProcessOptions {
+        self.options
+    }
+
+    fn current_dir(&self) -> Option<&Path> {
+        self.cwd.as_deref()
+    }
+}
+
+pub fn generate_exec<T: Store>(store: &T, args: &[String]) -> GenericResult<Command> {
+    let mut expanded = args.iter().map(|arg| template::expand_template(store, arg));
+    let program = expanded
+        .next()
+        .filter(|program| !program.is_empty())
+        .ok_or_else(|| CustomError::new("exec: program is empty"))?;
+    let mut command = Command::new(program);
+    command.envs(store.iter());
+    command.args(expanded);
+    Ok(command)
 }
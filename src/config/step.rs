@@ -0,0 +1,283 @@
+use std::{
+    collections::HashMap,
+    os::unix::process::ExitStatusExt,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use serde::{
+    de::{self, value::MapAccessDeserializer, MapAccess, Visitor},
+    Deserialize,
+};
+
+use crate::{
+    builtin,
+    config::{duration, shell},
+    error::BuiltInCommandErrorKind,
+    exit_code,
+    runner::{self, Capture, Outcome, ProcessOptions},
+    store::Store,
+    template,
+    utils::{self, CommandGenerator, GenericResult},
+};
+
+pub enum StepCommand {
+    Shell(String),
+    Exec(Vec<String>),
+    Template(String),
+    BuiltIn(Vec<String>),
+}
+
+pub struct Step {
+    pub command: StepCommand,
+    pub cwd: Option<String>,
+    pub env: HashMap<String, String>,
+    pub allow_failure: bool,
+    pub condition: Option<String>,
+    pub capture: Option<String>,
+    pub timeout: Option<Duration>,
+}
+impl Step {
+    pub fn shell(command: String) -> Self {
+        Self::new(StepCommand::Shell(command))
+    }
+
+    pub fn exec(args: Vec<String>) -> Self {
+        Self::new(StepCommand::Exec(args))
+    }
+
+    pub fn builtin(args: Vec<String>) -> Self {
+        Self::new(StepCommand::BuiltIn(args))
+    }
+
+    fn new(command: StepCommand) -> Self {
+        Self {
+            command,
+            cwd: None,
+            env: HashMap::new(),
+            allow_failure: false,
+            condition: None,
+            capture: None,
+            timeout: None,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match &self.command {
+            StepCommand::Shell(command) => command.to_owned(),
+            StepCommand::Exec(args) => args.join(" "),
+            StepCommand::Template(template) => template.to_owned(),
+            StepCommand::BuiltIn(args) => format!("builtin {}", args.join(" ")),
+        }
+    }
+
+    pub fn is_enabled<T: Store>(&self, store: &T) -> bool {
+        self.condition
+            .as_ref()
+            .is_none_or(|condition| template::evaluate_condition(store, condition))
+    }
+
+    fn generate<Tc: CommandGenerator, Ts: Store>(
+        &self,
+        cmdgen: &Tc,
+        store: &Ts,
+    ) -> GenericResult<Option<Command>> {
+        let mut command = match &self.command {
+            StepCommand::Shell(command) => {
+                let mut generated = cmdgen.generate(store);
+                generated.arg(command);
+                generated
+            }
+            StepCommand::Exec(args) => shell::generate_exec(store, args)?,
+            StepCommand::Template(_) | StepCommand::BuiltIn(_) => return Ok(None),
+        };
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(template::expand_template(store, cwd));
+        } else if let Some(cwd) = cmdgen.current_dir() {
+            command.current_dir(cwd);
+        }
+        for (key, value) in &self.env {
+            command.env(key, template::expand_template(store, value));
+        }
+        Ok(Some(command))
+    }
+
+    fn store_capture<T: Store>(store: &mut T, name: &str, value: String) {
+        if utils::is_verbose() {
+            eprintln!("{}={}", name, value);
+        }
+        store.set(name.to_owned(), value);
+    }
+
+    /// Runs the commands handled by depot itself instead of a child process.
+    fn execute_internal<Tc: CommandGenerator, Ts: Store>(
+        &self,
+        cmdgen: &Tc,
+        store: &mut Ts,
+    ) -> GenericResult<i32> {
+        let output = match &self.command {
+            StepCommand::Template(template) => Some(template::expand_template(store, template)),
+            StepCommand::BuiltIn(args) => {
+                let cwd = self
+                    .cwd
+                    .as_ref()
+                    .map(|cwd| PathBuf::from(template::expand_template(store, cwd)))
+                    .or_else(|| cmdgen.current_dir().map(Path::to_path_buf));
+                match builtin::run(store, cwd.as_deref(), args) {
+                    Ok(output) => output,
+                    // a misconfigured builtin is a config error, not a failed command
+                    Err(err) if err.kind() == BuiltInCommandErrorKind::InvalidArgument => {
+                        return Err(Box::new(err))
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        return Ok(match err.kind() {
+                            BuiltInCommandErrorKind::AlreadyExists => exit_code::CONFLICT,
+                            _ => exit_code::FAILURE,
+                        });
+                    }
+                }
+            }
+            _ => None,
+        };
+        if let Some(value) = output {
+            if let Some(name) = &self.capture {
+                Self::store_capture(store, name, value);
+            } else {
+                println!("{}", value);
+            }
+        }
+        Ok(exit_code::SUCCESS)
+    }
+
+    pub fn execute<Tc: CommandGenerator, Ts: Store>(
+        &self,
+        cmdgen: &Tc,
+        store: &mut Ts,
+    ) -> GenericResult<i32> {
+        let mut command = match self.generate(cmdgen, store)? {
+            Some(command) => command,
+            None => return self.execute_internal(cmdgen, store),
+        };
+        let options = ProcessOptions {
+            timeout: self.timeout.or(cmdgen.options().timeout),
+            ..cmdgen.options()
+        };
+        let capture = if self.capture.is_some() {
+            Capture::Stdout
+        } else {
+            Capture::None
+        };
+        match runner::run(&mut command, &options, capture)? {
+            Outcome::Exited(output) => {
+                if let Some(signal) = output.status.signal() {
+                    eprintln!(
+                        "killed by {}: {}",
+                        exit_code::signal_name(signal),
+                        self.describe()
+                    );
+                }
+                if let Some(name) = &self.capture {
+                    let value = options.decode(output.stdout)?.trim().to_owned();
+                    Self::store_capture(store, name, value);
+                }
+                Ok(exit_code::from_status(&output.status))
+            }
+            Outcome::TimedOut(timeout) => {
+                eprintln!("timed out after {:?}: {}", timeout, self.describe());
+                Ok(exit_code::TIMEOUT)
+            }
+            Outcome::Interrupted => {
+                eprintln!("interrupted: {}", self.describe());
+                Ok(exit_code::INTERRUPTED)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StepTable {
+    run: Option<String>,
+    exec: Option<Vec<String>>,
+    template: Option<String>,
+    builtin: Option<Vec<String>>,
+    cwd: Option<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    #[serde(default, alias = "continue_on_error")]
+    allow_failure: bool,
+    #[serde(rename = "if")]
+    condition: Option<String>,
+    capture: Option<String>,
+    #[serde(default, deserialize_with = "duration::deserialize_option")]
+    timeout: Option<Duration>,
+}
+
+impl<'de> Deserialize<'de> for Step {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct StepVisitor;
+        impl<'de> Visitor<'de> for StepVisitor {
+            type Value = Step;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(formatter, "step")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Step::shell(v.to_owned()))
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Step::shell(v))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let table = StepTable::deserialize(MapAccessDeserializer::new(map))?;
+                let command = match (table.run, table.exec, table.template, table.builtin) {
+                    (Some(run), None, None, None) => StepCommand::Shell(run),
+                    (None, Some(exec), None, None) if !exec.is_empty() => StepCommand::Exec(exec),
+                    (None, Some(_), None, None) => {
+                        return Err(de::Error::invalid_length(0, &"program"))
+                    }
+                    (None, None, Some(template), None) => StepCommand::Template(template),
+                    (None, None, None, Some(builtin)) if !builtin.is_empty() => {
+                        StepCommand::BuiltIn(builtin)
+                    }
+                    (None, None, None, Some(_)) => {
+                        return Err(de::Error::invalid_length(0, &"builtin name"))
+                    }
+                    _ => {
+                        return Err(de::Error::custom(
+                            "step requires exactly one of `run`, `exec`, `template` or `builtin`",
+                        ))
+                    }
+                };
+                Ok(Step {
+                    command,
+                    cwd: table.cwd,
+                    env: table.env,
+                    allow_failure: table.allow_failure,
+                    condition: table.condition,
+                    capture: table.capture,
+                    timeout: table.timeout,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(StepVisitor)
+    }
+}
@@ -19,16 +19,35 @@ impl Display for CustomError {
 impl StdError for CustomError {
 }
 
+#[derive(std::fmt::Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltInCommandErrorKind {
+    Other,
+    InvalidArgument,
+    NotFound,
+    AlreadyExists,
+    Io,
+}
+
 #[derive(std::fmt::Debug)]
 pub struct BuiltInCommandError {
-    message: String
+    message: String,
+    kind: BuiltInCommandErrorKind,
 }
 
 impl BuiltInCommandError {
     pub fn new(message: &str) -> Self {
-        return Self {
-            message: message.to_owned()
-        };
+        Self::with_kind(BuiltInCommandErrorKind::Other, message)
+    }
+
+    pub fn with_kind(kind: BuiltInCommandErrorKind, message: &str) -> Self {
+        Self {
+            message: message.to_owned(),
+            kind,
+        }
+    }
+
+    pub fn kind(&self) -> BuiltInCommandErrorKind {
+        self.kind
     }
 }
 
@@ -62,3 +81,25 @@ impl Display for PathStringifyError {
 
 impl StdError for PathStringifyError {
 }
+
+#[derive(std::fmt::Debug)]
+pub struct ProcessError {
+    message: String
+}
+
+impl ProcessError {
+    pub fn new(message: &str) -> Self {
+        return Self {
+            message: message.to_owned()
+        };
+    }
+}
+
+impl Display for ProcessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", &self.message)
+    }
+}
+
+impl StdError for ProcessError {
+}
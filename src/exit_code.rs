@@ -0,0 +1,60 @@
+//! Exit codes returned by depot.
+//!
+//! | code    | meaning                                                       |
+//! |---------|---------------------------------------------------------------|
+//! | 0       | success                                                       |
+//! | 1       | depot itself failed (config, I/O, ...)                        |
+//! | 1..=255 | exit code of the failed command, passed through as is, except |
+//! |         | 124, 125 and 255, which a command's exit turns into 1         |
+//! | 124     | a command ran longer than its timeout and was killed          |
+//! | 125     | the local path already exists (see `on_conflict`)             |
+//! | 128 + N | a command was killed by signal N (e.g. 137 for SIGKILL)       |
+//! | 130     | interrupted by Ctrl-C (SIGINT)                                |
+//! | 255     | the behavior is `not-supported` for the address               |
+
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+
+pub const SUCCESS: i32 = 0;
+pub const FAILURE: i32 = 1;
+pub const TIMEOUT: i32 = 124;
+pub const CONFLICT: i32 = 125;
+pub const SIGNAL_BASE: i32 = 128;
+pub const INTERRUPTED: i32 = SIGNAL_BASE + libc::SIGINT;
+pub const NOT_SUPPORTED: i32 = 255;
+
+/// Exit code of a finished command; codes reserved by depot itself become `FAILURE`.
+pub fn from_status(status: &ExitStatus) -> i32 {
+    match from_subcommand_status(status) {
+        TIMEOUT | CONFLICT | NOT_SUPPORTED if status.code().is_some() => FAILURE,
+        code => code,
+    }
+}
+
+/// Exit code of an external `depot-<name>` subcommand, which shares the codes above.
+pub fn from_subcommand_status(status: &ExitStatus) -> i32 {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => code,
+        (None, Some(signal)) => SIGNAL_BASE + signal,
+        (None, None) => FAILURE,
+    }
+}
+
+pub fn signal_name(signal: i32) -> String {
+    let name = match signal {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        _ => return format!("signal {}", signal),
+    };
+    name.to_owned()
+}
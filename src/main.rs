@@ -2,11 +2,16 @@ use clap::Parser;
 use std::env;
 use std::path::PathBuf;
 use std::process;
+use std::time::Duration;
 
+mod builtin;
 mod config;
 mod error;
+mod exit_code;
+mod runner;
 mod store;
 mod subcommands;
+mod table;
 mod template;
 mod utils;
 
@@ -21,15 +26,24 @@ struct Cli {
     #[clap(short, long, parse(from_occurrences))]
     debug: usize,
 
+    /// Print variables captured by behavior steps and list directories the walk could not read
+    #[clap(short, long)]
+    verbose: bool,
+
     #[clap(short = 'e', long = "reset_env", default_value_t = false)]
     pub reset_environment: bool,
 
+    /// Kills commands that run longer than this (e.g. 30s, 10m); 0 disables, overrides shell.timeout
+    #[clap(long, parse(try_from_str = utils::parse_duration), value_name = "DURATION")]
+    timeout: Option<Duration>,
+
     #[clap(subcommand)]
     subcommand: subcommands::Subcommands,
 }
 
 fn main() {
     let cli = Cli::parse();
+    utils::set_verbose(cli.verbose);
     let config_file: PathBuf;
 
     if let Some(path) = cli.config {
@@ -41,10 +55,13 @@ fn main() {
         config_file = PathBuf::from(path);
     }
 
-    let config = config::load_from_file(config_file).unwrap_or_else(|err| {
+    let mut config = config::load_from_file(config_file).unwrap_or_else(|err| {
         eprintln!("config file load failed: {}", err);
-        process::exit(1)
+        process::exit(exit_code::FAILURE)
     });
+    if cli.timeout.is_some() {
+        config.shell.timeout = cli.timeout;
+    }
 
     let store = if cli.reset_environment {
         store::EnvironmentStore::new_env()
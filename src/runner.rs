@@ -0,0 +1,226 @@
+use std::io::{self, Read};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Once;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::error::ProcessError;
+use crate::utils::GenericResult;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+static INSTALL_HANDLER: Once = Once::new();
+
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(2);
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy)]
+pub struct ProcessOptions {
+    pub timeout: Option<Duration>,
+    pub foreground: bool,
+    pub lossy_utf8: bool,
+}
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            foreground: true,
+            lossy_utf8: false,
+        }
+    }
+}
+impl ProcessOptions {
+    pub fn decode(&self, bytes: Vec<u8>) -> GenericResult<String> {
+        if self.lossy_utf8 {
+            Ok(String::from_utf8_lossy(&bytes).into_owned())
+        } else {
+            String::from_utf8(bytes).map_err(|err| {
+                Box::new(ProcessError::new(&format!(
+                    "command output is not valid UTF-8: {}",
+                    err
+                )))
+                .into()
+            })
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Capture {
+    None,
+    Stdout,
+    All,
+}
+
+pub enum Outcome {
+    Exited(Output),
+    TimedOut(Duration),
+    Interrupted,
+}
+
+extern "C" fn handle_interrupt(signal: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    if RUNNING.load(Ordering::SeqCst) == 0 {
+        // nothing to cancel, behave as if no handler was installed
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+}
+
+fn install_interrupt_handler() {
+    INSTALL_HANDLER.call_once(|| unsafe {
+        libc::signal(
+            libc::SIGINT,
+            handle_interrupt as *const () as libc::sighandler_t,
+        );
+        libc::signal(
+            libc::SIGTERM,
+            handle_interrupt as *const () as libc::sighandler_t,
+        );
+    });
+}
+
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+fn is_terminal_foreground() -> bool {
+    unsafe {
+        libc::isatty(libc::STDIN_FILENO) == 1
+            && libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp()
+    }
+}
+
+unsafe fn set_terminal_foreground(pgid: libc::pid_t) {
+    let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+    libc::tcsetpgrp(libc::STDIN_FILENO, pgid);
+    libc::signal(libc::SIGTTOU, previous);
+}
+
+fn kill_group(pgid: libc::pid_t, signal: libc::c_int) {
+    unsafe {
+        libc::kill(-pgid, signal);
+    }
+}
+
+/// Runs the command in its own process group, so that a timeout or an interrupt can kill everything it started.
+/// When depot owns the terminal, the group is moved to the foreground while it runs so that prompts keep working.
+pub fn run(
+    command: &mut Command,
+    options: &ProcessOptions,
+    capture: Capture,
+) -> GenericResult<Outcome> {
+    install_interrupt_handler();
+    if is_interrupted() {
+        return Ok(Outcome::Interrupted);
+    }
+
+    let foreground = options.foreground && is_terminal_foreground();
+    unsafe {
+        command.pre_exec(move || {
+            libc::setpgid(0, 0);
+            if foreground {
+                set_terminal_foreground(libc::getpid());
+            }
+            Ok(())
+        });
+    }
+    if capture != Capture::None {
+        command.stdout(Stdio::piped());
+    }
+    if capture == Capture::All {
+        command.stderr(Stdio::piped());
+    }
+
+    RUNNING.fetch_add(1, Ordering::SeqCst);
+    let result = wait(command, options, foreground);
+    RUNNING.fetch_sub(1, Ordering::SeqCst);
+    if foreground {
+        unsafe { set_terminal_foreground(libc::getpgrp()) };
+    }
+    result
+}
+
+fn wait(
+    command: &mut Command,
+    options: &ProcessOptions,
+    foreground: bool,
+) -> GenericResult<Outcome> {
+    let mut child = command.spawn()?;
+    let pgid = child.id() as libc::pid_t;
+    unsafe {
+        libc::setpgid(pgid, pgid);
+        if foreground {
+            set_terminal_foreground(pgid);
+        }
+    }
+
+    let stdout_reader = child.stdout.take().map(spawn_reader);
+    let stderr_reader = child.stderr.take().map(spawn_reader);
+
+    let started = Instant::now();
+    let mut interval = Duration::from_millis(1);
+    let mut killed_at: Option<Instant> = None;
+    let mut cancelled: Option<Outcome> = None;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if let Some(killed_at) = killed_at {
+            if killed_at.elapsed() > KILL_GRACE_PERIOD {
+                kill_group(pgid, libc::SIGKILL);
+            }
+        } else if is_interrupted() {
+            cancelled = Some(Outcome::Interrupted);
+        } else if let Some(timeout) = options
+            .timeout
+            .filter(|timeout| !timeout.is_zero() && started.elapsed() > *timeout)
+        {
+            cancelled = Some(Outcome::TimedOut(timeout));
+        }
+        if cancelled.is_some() && killed_at.is_none() {
+            kill_group(pgid, libc::SIGTERM);
+            killed_at = Some(Instant::now());
+        }
+        thread::sleep(interval);
+        interval = (interval * 2).min(MAX_POLL_INTERVAL);
+    };
+    // reap anything the command left behind in its group
+    if killed_at.is_some() {
+        kill_group(pgid, libc::SIGKILL);
+    }
+
+    let stdout = join_reader(stdout_reader)?;
+    let stderr = join_reader(stderr_reader)?;
+    if let Some(outcome) = cancelled {
+        return Ok(outcome);
+    }
+    if status.signal() == Some(libc::SIGINT) {
+        // the terminal delivered Ctrl-C to the foreground group instead of depot
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        return Ok(Outcome::Interrupted);
+    }
+    Ok(Outcome::Exited(Output {
+        status,
+        stdout,
+        stderr,
+    }))
+}
+
+fn spawn_reader<R: Read + Send + 'static>(mut source: R) -> JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        source.read_to_end(&mut buffer).map(|_| buffer)
+    })
+}
+
+fn join_reader(reader: Option<JoinHandle<io::Result<Vec<u8>>>>) -> io::Result<Vec<u8>> {
+    match reader {
+        Some(reader) => reader.join().unwrap_or_else(|_| Ok(Vec::new())),
+        None => Ok(Vec::new()),
+    }
+}
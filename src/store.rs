@@ -2,7 +2,21 @@ use std::{collections::HashMap, env};
 
 use url::Url;
 
-pub trait Store: Clone {
+/// Upper-cases `name` and replaces everything but ASCII letters and digits with `_`, after `prefix`.
+fn var_name(prefix: &str, name: &str) -> String {
+    let key: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("{}{}", prefix, key)
+}
+
+/// Name of the variable holding the value of the `list` field `name`, e.g. `DEPOT_FIELD_GIT_BRANCH`.
+pub fn field_var_name(name: &str) -> String {
+    var_name("DEPOT_FIELD_", name)
+}
+
+pub trait Store: Clone + Send {
     fn get(&self, key: &str) -> Option<&str>;
     fn set(&mut self, key: String, value: String);
     fn set_remote_raw(&mut self, remote_url_raw: &str) {
@@ -41,9 +55,15 @@ pub trait Store: Clone {
         self.set("DEPOT_LOCAL_PATH".to_owned(), local_path);
         self.set("DEPOT_LOCAL_REL_PATH".to_owned(), rel_local_path);
     }
+    fn set_staging_path(&mut self, staging_path: String) {
+        self.set("DEPOT_STAGING_PATH".to_owned(), staging_path);
+    }
     fn set_root_path(&mut self, root_path: String) {
         self.set("DEPOT_ROOT_PATH".to_owned(), root_path);
     }
+    fn set_project_kind(&mut self, kind: String) {
+        self.set("DEPOT_PROJECT_KIND".to_owned(), kind);
+    }
     fn set_source_remote_raw(&mut self, source_raw: String) {
         self.set("DEPOT_SOURCE_REMOTE_RAW".to_owned(), source_raw);
     }
@@ -80,6 +100,15 @@ pub trait Store: Clone {
         self.set("DEPOT_SOURCE_LOCAL_PATH".to_owned(), source_path);
         self.set("DEPOT_SOURCE_LOCAL_REL_PATH".to_owned(), rel_source_path);
     }
+    fn set_overload_name(&mut self, name: &str) {
+        self.set("DEPOT_OVERLOAD_NAME".to_owned(), name.to_owned());
+    }
+    fn set_overload_var(&mut self, name: &str, value: String) {
+        self.set(var_name("DEPOT_VAR_", name), value);
+    }
+    fn set_field_value(&mut self, name: &str, value: String) {
+        self.set(field_var_name(name), value);
+    }
 
     fn iter(&self) -> impl Iterator<Item = (&'_ str, &'_ str)>;
 }
@@ -1,14 +1,26 @@
-use crate::{config::Config, store::Store, utils::GenericResult};
+use crate::{config::Config, exit_code, store::Store, utils::GenericResult};
 use clap;
 
+mod archive;
+mod conflict;
 mod create;
 mod external;
+mod field_cache;
+mod foreach;
+mod fs_visitor;
 mod get;
 mod get_overload;
 mod list;
+mod presets;
 mod r#move;
+mod parallel;
 mod resolve;
 mod root;
+mod selection;
+mod staging;
+mod status;
+mod unarchive;
+mod update;
 
 pub trait Subcommand {
     fn run(&self, config: &Config, store: impl Store) -> GenericResult<i32>;
@@ -69,6 +81,12 @@ define_subcommands! {
     (Get, get);
     (Create, create);
     (Move, r#move);
+    (Update, update);
+    (Foreach, foreach);
+    (Status, status);
+    (Archive, archive);
+    (Unarchive, unarchive);
+    (Presets, presets);
 }
 
 pub fn run(config: &Config, command: Subcommands, store: impl Store) -> i32 {
@@ -78,7 +96,7 @@ pub fn run(config: &Config, command: Subcommands, store: impl Store) -> i32 {
         }
         Err(err) => {
             eprintln!("{}", err);
-            return 1;
+            return exit_code::FAILURE;
         }
     }
 }
@@ -0,0 +1,240 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+use crate::builtin;
+use crate::config::archive_format::ArchiveFormat;
+use crate::config::overload::Overload;
+use crate::config::shell::CompiledShell;
+use crate::config::Config;
+use crate::error::ProcessError;
+use crate::exit_code;
+use crate::runner::{self, Capture, Outcome};
+use crate::store::Store;
+use crate::utils::{self, CommandGenerator, GenericResult};
+
+pub const ABOUT: &str = "move a project into the archive area";
+
+#[derive(Args)]
+pub struct Subcommand {
+    /// Address of the project to archive
+    address: String,
+
+    /// Overrides `subcommands.archive.format`
+    #[clap(long, arg_enum, value_name = "FORMAT")]
+    format: Option<ArchiveFormat>,
+}
+
+/// Written next to every archived project so that it can be listed and restored.
+#[derive(Serialize, Deserialize)]
+pub struct Metadata {
+    pub address: String,
+    pub overload: Option<String>,
+    pub rel_path: String,
+    pub format: ArchiveFormat,
+    pub archived_at: u64,
+}
+
+/// The directory given by `subcommands.archive.root`. A project archived from `host/user/repo`
+/// is stored as `host%user%repo` (a directory or `host%user%repo.tar.gz`) with `host%user%repo.json`;
+/// a `%` in a path segment is written as `%25`.
+pub struct ArchiveArea {
+    pub path: PathBuf,
+}
+impl ArchiveArea {
+    /// Expects `DEPOT_ROOT_PATH` to be set in the store.
+    pub fn new<Ts: Store>(
+        config: &Config,
+        cmdgen: &CompiledShell,
+        store: &Ts,
+    ) -> GenericResult<Self> {
+        let path = config.subcommands.archive.root.expand(cmdgen, store)?;
+        Ok(Self {
+            path: PathBuf::from(utils::concat_path(&path, "")),
+        })
+    }
+
+    pub fn entry_name(rel_path: &str) -> String {
+        rel_path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| segment.replace('%', "%25"))
+            .collect::<Vec<String>>()
+            .join("%")
+    }
+
+    pub fn metadata_path(&self, name: &str) -> PathBuf {
+        self.path.join(format!("{}.json", name))
+    }
+
+    pub fn data_path(&self, name: &str, format: ArchiveFormat) -> PathBuf {
+        match format {
+            ArchiveFormat::Directory => self.path.join(name),
+            ArchiveFormat::TarGz => self.path.join(format!("{}.tar.gz", name)),
+        }
+    }
+
+    pub fn read_metadata(&self, name: &str) -> GenericResult<Metadata> {
+        Ok(serde_json::from_slice(&fs::read(
+            self.metadata_path(name),
+        )?)?)
+    }
+
+    /// Returns every archived project with its entry name, sorted by name.
+    pub fn entries(&self) -> GenericResult<Vec<(String, Metadata)>> {
+        let read_dir = match fs::read_dir(&self.path) {
+            Ok(read_dir) => read_dir,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(Box::new(err)),
+        };
+        let mut entries = Vec::new();
+        for entry in read_dir {
+            let file_name = entry?.file_name();
+            let Some(name) = file_name
+                .to_str()
+                .and_then(|name| name.strip_suffix(".json"))
+            else {
+                continue;
+            };
+            match self.read_metadata(name) {
+                Ok(metadata) => entries.push((name.to_owned(), metadata)),
+                Err(err) => eprintln!("warning: {}: {}", self.metadata_path(name).display(), err),
+            }
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+}
+
+/// Resolves the address like `get` does and sets the local path in the store.
+pub fn resolve_address<'a, Ts: Store>(
+    config: &'a Config,
+    cmdgen: &CompiledShell,
+    store: &mut Ts,
+    address: &str,
+) -> GenericResult<(PathBuf, String, Option<&'a Overload>)> {
+    let remote_url = config.parse.parse_url(address)?;
+    let overload = config.overloads.find_overload(address, &remote_url);
+    store.set_remote_raw(address);
+    store.set_remote_url(&remote_url);
+    if let Some(overload) = overload {
+        overload.export(store);
+    }
+
+    let root_path = store.get("DEPOT_ROOT_PATH").unwrap_or_default().to_owned();
+    let rel_path = config.resolve.expand_path(cmdgen, store, overload)?;
+    let path = utils::concat_path(&root_path, &rel_path);
+    store.set_local_path(path.clone(), rel_path.clone());
+    Ok((PathBuf::from(path), rel_path, overload))
+}
+
+/// Runs `tar` with the given arguments, failing unless it exits successfully.
+pub fn run_tar(cmdgen: &CompiledShell, args: &[&Path]) -> GenericResult<()> {
+    let mut command = Command::new("tar");
+    command.args(args);
+    let description = format!(
+        "tar {}",
+        args.iter()
+            .map(|arg| arg.display().to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    );
+    match runner::run(&mut command, &cmdgen.options(), Capture::None)? {
+        Outcome::Exited(output) if output.status.success() => Ok(()),
+        Outcome::Exited(output) => Err(Box::new(ProcessError::new(&format!(
+            "exit {}: {}",
+            exit_code::from_status(&output.status),
+            description
+        )))),
+        Outcome::TimedOut(timeout) => Err(Box::new(ProcessError::new(&format!(
+            "timed out after {:?}: {}",
+            timeout, description
+        )))),
+        Outcome::Interrupted => Err(Box::new(ProcessError::new(&format!(
+            "interrupted: {}",
+            description
+        )))),
+    }
+}
+
+fn store_data(
+    cmdgen: &CompiledShell,
+    format: ArchiveFormat,
+    path: &Path,
+    data_path: &Path,
+    root: &Path,
+) -> GenericResult<()> {
+    match format {
+        ArchiveFormat::Directory => Ok(builtin::move_path(path, data_path)?),
+        ArchiveFormat::TarGz => {
+            // compressed aside and renamed so that an interrupted `tar` leaves no partial archive
+            let mut temporary = data_path.as_os_str().to_owned();
+            temporary.push(format!(".{}", process::id()));
+            let temporary = PathBuf::from(temporary);
+            let result = run_tar(
+                cmdgen,
+                &[
+                    Path::new("-czf"),
+                    &temporary,
+                    Path::new("-C"),
+                    path,
+                    Path::new("."),
+                ],
+            )
+            .and_then(|()| Ok(fs::rename(&temporary, data_path)?));
+            if let Err(err) = result {
+                let _ = fs::remove_file(&temporary);
+                return Err(err);
+            }
+            Ok(builtin::remove(path, Some(root))?)
+        }
+    }
+}
+
+impl super::Subcommand for Subcommand {
+    fn run(&self, config: &Config, mut store: impl Store) -> GenericResult<i32> {
+        let cmdgen = config.shell.compile(&store)?;
+        let root_path = config.core.root.expand(&cmdgen, &store)?;
+        store.set_root_path(root_path.clone());
+
+        let (path, rel_path, overload) =
+            resolve_address(config, &cmdgen, &mut store, &self.address)?;
+        let area = ArchiveArea::new(config, &cmdgen, &store)?;
+        let name = ArchiveArea::entry_name(&rel_path);
+        let metadata_path = area.metadata_path(&name);
+        if metadata_path.exists() {
+            eprintln!("{}: already archived in {}", rel_path, area.path.display());
+            return Ok(exit_code::CONFLICT);
+        }
+        if !path.is_dir() {
+            eprintln!("{}: not found", path.display());
+            return Ok(exit_code::FAILURE);
+        }
+
+        let format = self.format.unwrap_or(config.subcommands.archive.format);
+        let metadata = Metadata {
+            address: self.address.clone(),
+            overload: overload.map(|overload| overload.name.clone()),
+            rel_path,
+            format,
+            archived_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|now| now.as_secs())
+                .unwrap_or_default(),
+        };
+        fs::create_dir_all(&area.path)?;
+        fs::write(&metadata_path, serde_json::to_vec_pretty(&metadata)?)?;
+
+        let data_path = area.data_path(&name, format);
+        if let Err(err) = store_data(&cmdgen, format, &path, &data_path, Path::new(&root_path)) {
+            let _ = fs::remove_file(&metadata_path);
+            return Err(err);
+        }
+        println!("{}", data_path.display());
+        Ok(exit_code::SUCCESS)
+    }
+}
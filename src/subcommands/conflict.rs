@@ -0,0 +1,73 @@
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use clap::Args;
+
+use crate::builtin;
+use crate::config::behavior::Behavior;
+use crate::config::conflict::ConflictPolicy;
+use crate::exit_code;
+use crate::store::Store;
+use crate::utils::{CommandGenerator, GenericResult};
+
+#[derive(Args)]
+pub struct ConflictArgs {
+    /// What to do when the local path already exists; overrides `on_conflict`
+    #[clap(long, arg_enum, value_name = "POLICY")]
+    on_conflict: Option<ConflictPolicy>,
+
+    /// Overwrite without asking for confirmation
+    #[clap(short = 'y', long)]
+    yes: bool,
+}
+
+impl ConflictArgs {
+    /// Applies the conflict policy when `DEPOT_LOCAL_PATH` already exists.
+    /// Returns the exit code if the conflict was handled, or `None` to go on with `command`.
+    pub fn check<Tc: CommandGenerator, Ts: Store>(
+        &self,
+        configured: ConflictPolicy,
+        update_command: &Behavior,
+        cmdgen: &Tc,
+        store: &mut Ts,
+    ) -> GenericResult<Option<i32>> {
+        let path = store.get("DEPOT_LOCAL_PATH").unwrap_or_default().to_owned();
+        if fs::symlink_metadata(&path).is_err() {
+            return Ok(None);
+        }
+        match self.on_conflict.unwrap_or(configured) {
+            ConflictPolicy::Error => {
+                eprintln!("{}: already exists", path);
+                Ok(Some(exit_code::CONFLICT))
+            }
+            ConflictPolicy::Skip => {
+                println!("{}", path);
+                Ok(Some(exit_code::SUCCESS))
+            }
+            ConflictPolicy::Update => update_command.execute(cmdgen, store).map(Some),
+            ConflictPolicy::Overwrite => {
+                if !self.yes && !confirm(&path)? {
+                    eprintln!("{}: already exists, not overwritten", path);
+                    return Ok(Some(exit_code::CONFLICT));
+                }
+                let root = store.get("DEPOT_ROOT_PATH").map(Path::new);
+                builtin::remove(Path::new(&path), root)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+fn confirm(path: &str) -> GenericResult<bool> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        eprintln!("{}: refusing to overwrite without a terminal, pass --yes", path);
+        return Ok(false);
+    }
+    eprint!("{} already exists, overwrite? [y/N] ", path);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
@@ -1,6 +1,7 @@
 use std::process::Command;
 
 use crate::config::Config;
+use crate::exit_code;
 use crate::store::Store;
 use crate::utils::GenericResult;
 use clap::Args;
@@ -23,6 +24,6 @@ impl super::Subcommand for Subcommand {
         cmd.envs(store.iter());
         let mut process = cmd.spawn()?;
         let exit_status = process.wait()?;
-        return Ok(exit_status.code().unwrap_or(1));
+        return Ok(exit_code::from_subcommand_status(&exit_status));
     }
 }
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::utils::{self, GenericResult};
+
+/// Modification times of the files a cached value depends on, in nanoseconds; `None` if missing.
+pub type Stamp = Vec<Option<u64>>;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    value: String,
+    /// Milliseconds since the epoch.
+    computed_at: u64,
+    stamp: Stamp,
+    /// Values of the fields this one refers to.
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct CacheFile {
+    projects: BTreeMap<String, BTreeMap<String, Entry>>,
+}
+
+/// Values of `list` fields with a `cache` setting, kept in `<root>/.depot/cache/fields.json`.
+pub struct FieldValueCache {
+    root: PathBuf,
+    path: PathBuf,
+    file: CacheFile,
+    dirty: bool,
+}
+impl FieldValueCache {
+    /// A missing or unreadable cache file starts an empty cache.
+    pub fn load(root: &Path) -> Self {
+        let path = root.join(utils::STATE_DIR_NAME).join("cache").join("fields.json");
+        let file = match fs::read(&path) {
+            Ok(contents) => serde_json::from_slice(&contents).unwrap_or_else(|err| {
+                eprintln!("warning: {}: ignoring broken cache: {}", path.display(), err);
+                CacheFile::default()
+            }),
+            Err(_) => CacheFile::default(),
+        };
+        Self {
+            root: root.to_path_buf(),
+            path,
+            file,
+            dirty: false,
+        }
+    }
+
+    pub fn stamp<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Stamp {
+        paths
+            .into_iter()
+            .map(|path| {
+                fs::metadata(path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+                    .map(|mtime| mtime.as_nanos() as u64)
+            })
+            .collect()
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_millis() as u64)
+            .unwrap_or_default()
+    }
+
+    /// Returns the cached value if it was computed with the same stamp and dependency values,
+    /// and is not older than `ttl`.
+    pub fn get(
+        &self,
+        rel_path: &str,
+        field: &str,
+        stamp: &Stamp,
+        dependencies: &[String],
+        ttl: Option<Duration>,
+    ) -> Option<&str> {
+        let entry = self.file.projects.get(rel_path)?.get(field)?;
+        let age = Duration::from_millis(Self::now().saturating_sub(entry.computed_at));
+        let fresh = ttl.is_none_or(|ttl| age < ttl);
+        (fresh && &entry.stamp == stamp && entry.dependencies == dependencies)
+            .then_some(entry.value.as_str())
+    }
+
+    pub fn insert(
+        &mut self,
+        rel_path: &str,
+        field: &str,
+        stamp: Stamp,
+        dependencies: Vec<String>,
+        value: String,
+    ) {
+        self.file.projects.entry(rel_path.to_owned()).or_default().insert(
+            field.to_owned(),
+            Entry {
+                value,
+                computed_at: Self::now(),
+                stamp,
+                dependencies,
+            },
+        );
+        self.dirty = true;
+    }
+
+    /// Writes the cache if it changed, forgetting projects that no longer exist.
+    pub fn save(&mut self) -> GenericResult<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let root = &self.root;
+        self.file
+            .projects
+            .retain(|rel_path, _| root.join(rel_path).exists());
+
+        let parent = self.path.parent().unwrap_or(root);
+        fs::create_dir_all(parent)?;
+        // written aside and renamed so that a concurrent `list` never reads a partial file
+        let temporary = parent.join(format!("fields.json.{}", process::id()));
+        fs::write(&temporary, serde_json::to_vec(&self.file)?)?;
+        if let Err(err) = fs::rename(&temporary, &self.path) {
+            let _ = fs::remove_file(&temporary);
+            return Err(Box::new(err));
+        }
+        self.dirty = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use clap::Args;
+
+use crate::config::shell::{self, CompiledShell};
+use crate::config::Config;
+use crate::exit_code;
+use crate::runner::{self, Capture, Outcome};
+use crate::store::Store;
+use crate::utils::{CommandGenerator, GenericResult};
+
+use super::parallel;
+use super::selection::{ProjectSelection, SelectedProject};
+
+pub const ABOUT: &str = "run a command in every project directory";
+
+#[derive(Args)]
+#[clap(trailing_var_arg = true)]
+pub struct Subcommand {
+    #[clap(flatten)]
+    selection: ProjectSelection,
+
+    /// Number of projects processed in parallel [default: 1]
+    #[clap(short, long)]
+    jobs: Option<usize>,
+
+    /// Prefixes every output line with the project path instead of printing a header per project
+    #[clap(short, long)]
+    prefix: bool,
+
+    /// A single argument runs through the configured shell; several run as a program and its arguments
+    #[clap(required = true, multiple_values = true)]
+    command: Vec<String>,
+}
+
+struct ProjectResult {
+    rel_path: String,
+    code: i32,
+}
+
+impl Subcommand {
+    /// Output is captured and printed as a whole, unless a single project at a time writes straight to the terminal.
+    fn capture(&self, jobs: usize) -> Capture {
+        if jobs > 1 || self.prefix {
+            Capture::All
+        } else {
+            Capture::None
+        }
+    }
+
+    fn run_project<Ts: Store>(
+        &self,
+        cmdgen: &CompiledShell,
+        project: SelectedProject<'_, Ts>,
+        capture: Capture,
+        output_lock: &Mutex<()>,
+    ) -> GenericResult<i32> {
+        let mut command = if self.command.len() == 1 {
+            let mut command = cmdgen.generate(&project.store);
+            command.arg(&self.command[0]);
+            command
+        } else {
+            shell::generate_exec(&project.store, &self.command)?
+        };
+        command.current_dir(&project.path);
+
+        if capture == Capture::None {
+            println!("==> {} <==", project.rel_path);
+        }
+        let outcome = runner::run(&mut command, &cmdgen.options, capture)?;
+        let (code, stdout, stderr) = match outcome {
+            Outcome::Exited(output) => (
+                exit_code::from_status(&output.status),
+                output.stdout,
+                output.stderr,
+            ),
+            Outcome::TimedOut(timeout) => (
+                exit_code::TIMEOUT,
+                Vec::new(),
+                format!("timed out after {:?}\n", timeout).into_bytes(),
+            ),
+            Outcome::Interrupted => (exit_code::INTERRUPTED, Vec::new(), Vec::new()),
+        };
+        if capture != Capture::None {
+            let _guard = output_lock.lock().unwrap();
+            self.print_output(&project.rel_path, &stdout, &stderr)?;
+        }
+        Ok(code)
+    }
+
+    fn print_output(&self, rel_path: &str, stdout: &[u8], stderr: &[u8]) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        let mut err = io::stderr().lock();
+        if self.prefix {
+            for line in String::from_utf8_lossy(stdout).lines() {
+                writeln!(out, "{}: {}", rel_path, line)?;
+            }
+            for line in String::from_utf8_lossy(stderr).lines() {
+                writeln!(err, "{}: {}", rel_path, line)?;
+            }
+        } else {
+            writeln!(out, "==> {} <==", rel_path)?;
+            out.write_all(stdout)?;
+            out.flush()?;
+            err.write_all(stderr)?;
+        }
+        Ok(())
+    }
+}
+
+impl super::Subcommand for Subcommand {
+    fn run(&self, config: &Config, mut store: impl Store) -> GenericResult<i32> {
+        let mut cmdgen = config.shell.compile(&store)?;
+        let root_path = config.core.root.expand(&cmdgen, &store)?;
+        store.set_root_path(root_path.clone());
+
+        let projects = self.selection.collect(config, &store, &root_path)?;
+        let jobs = parallel::jobs_for_shell(&mut cmdgen, Some(self.jobs.unwrap_or(1)), projects.len());
+        let capture = self.capture(jobs);
+
+        let output_lock = Mutex::new(());
+        let results = parallel::map(projects, jobs, |project| {
+            let rel_path = project.rel_path.clone();
+            if runner::is_interrupted() {
+                return ProjectResult {
+                    rel_path,
+                    code: exit_code::INTERRUPTED,
+                };
+            }
+            let code = self
+                .run_project(&cmdgen, project, capture, &output_lock)
+                .unwrap_or_else(|err| {
+                    eprintln!("{}: {}", rel_path, err);
+                    exit_code::FAILURE
+                });
+            ProjectResult { rel_path, code }
+        });
+
+        if runner::is_interrupted() {
+            return Ok(exit_code::INTERRUPTED);
+        }
+        let failed: Vec<String> = results
+            .iter()
+            .filter(|result| result.code != exit_code::SUCCESS)
+            .map(|result| format!("{} (exit {})", result.rel_path, result.code))
+            .collect();
+        if failed.is_empty() {
+            return Ok(exit_code::SUCCESS);
+        }
+        eprintln!(
+            "failed in {} of {} projects: {}",
+            failed.len(),
+            results.len(),
+            failed.join(", ")
+        );
+        Ok(exit_code::FAILURE)
+    }
+}
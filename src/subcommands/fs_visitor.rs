@@ -0,0 +1,305 @@
+use crate::config::directory_condition::CompiledDirectoryCondition;
+use crate::config::pattern::Pattern;
+use crate::config::overload::OverloadsContainer;
+use crate::config::Project;
+use crate::error::PathStringifyError;
+use crate::runner;
+use crate::utils::{self, Compilable, DirectoryMatcher, GenericResult, StringMatcher};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::fs::{self, Metadata};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Name of the gitignore-style files read in every directory of the walk.
+const IGNORE_FILE_NAME: &str = ".depotignore";
+
+/// Walks the depot root and hands every project directory to a visitor,
+/// without descending into projects (unless `nested_projects`) or excluded directories.
+///
+/// Every directory is entered at most once, identified by device and inode,
+/// so symlink loops and bind mounts cannot make the walk run forever.
+///
+/// A `.depotignore` file (gitignore syntax) in any directory hides the matching paths below it,
+/// in addition to the `excludes` patterns.
+pub struct FsVisitor<Tdm: DirectoryMatcher> {
+    root: PathBuf,
+    excludes: Vec<Box<dyn StringMatcher>>,
+    excluded_dirs: Vec<PathBuf>,
+    condition: Tdm,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    nested_projects: bool,
+    global_ignore: Option<Gitignore>,
+    ignores: RefCell<Vec<Gitignore>>,
+    stopped: Cell<bool>,
+    visited: RefCell<HashSet<(u64, u64)>>,
+    unreadable: Cell<usize>,
+}
+impl FsVisitor<CompiledDirectoryCondition> {
+    /// Walks the projects defined by `subcommands.list.project`, or recognized by the `condition` of an overload.
+    pub fn for_project(root: &str, project: &Project, overloads: &OverloadsContainer) -> GenericResult<Self> {
+        let condition = project.condition.compile()?.or_any(overloads.compile_conditions()?);
+        Ok(Self::new(root, &project.excludes, condition)?
+            .follow_symlinks(project.follow_symlinks)
+            .nested_projects(project.nested_projects)
+            .global_gitignore(project.global_gitignore))
+    }
+}
+impl<Tdm: DirectoryMatcher> FsVisitor<Tdm> {
+    pub fn new(root: &str, exclude_patterns: &Vec<Pattern>, condition: Tdm) -> GenericResult<Self> {
+        let mut excludes: Vec<Box<dyn StringMatcher>> = Vec::new();
+        for ptn in exclude_patterns {
+            excludes.push(ptn.compile()?)
+        }
+        Ok(Self {
+            root: PathBuf::from(root),
+            excludes,
+            excluded_dirs: Vec::new(),
+            condition,
+            max_depth: None,
+            follow_symlinks: true,
+            nested_projects: false,
+            global_ignore: None,
+            ignores: RefCell::new(Vec::new()),
+            stopped: Cell::new(false),
+            visited: RefCell::new(HashSet::new()),
+            unreadable: Cell::new(0),
+        })
+    }
+
+    /// Symlinked directories under the root are followed unless this is unset.
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Also looks for projects inside projects, such as submodules or monorepo packages.
+    pub fn nested_projects(mut self, nested_projects: bool) -> Self {
+        self.nested_projects = nested_projects;
+        self
+    }
+
+    /// Also hides the paths matched by the user's global gitignore (`core.excludesFile`).
+    pub fn global_gitignore(mut self, global_gitignore: bool) -> Self {
+        self.global_ignore = None;
+        if global_gitignore {
+            let (gitignore, err) = Gitignore::global();
+            if let Some(err) = err {
+                eprintln!("warning: global gitignore: {}", err);
+            }
+            self.global_ignore = Some(gitignore);
+        }
+        self
+    }
+
+    /// Skips a directory given by its absolute path, such as the archive area.
+    pub fn exclude_dir(mut self, path: PathBuf) -> Self {
+        self.excluded_dirs.push(path);
+        self
+    }
+
+    /// Directories deeper than `max_depth` levels below the root are neither checked nor descended into.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Ends the walk after the current project.
+    pub fn stop(&self) {
+        self.stopped.set(true);
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn convert_path_to_str(path: &Path) -> GenericResult<&str> {
+        path.to_str()
+            .ok_or(Box::new(PathStringifyError::new("convert path failed")))
+    }
+
+    fn accept_directory<F>(&self, path: &Path, visit: &mut F) -> GenericResult<()>
+    where
+        F: FnMut(&Path, &str) -> GenericResult<()>,
+    {
+        if let Ok(relpath) = path.strip_prefix(&self.root) {
+            if self
+                .max_depth
+                .is_some_and(|max_depth| relpath.components().count() >= max_depth)
+            {
+                return Ok(());
+            }
+        }
+        let read_dir = match path.read_dir() {
+            Ok(read_dir) => read_dir,
+            Err(err) => {
+                self.unreadable.set(self.unreadable.get() + 1);
+                if utils::is_verbose() {
+                    eprintln!("{}: {}", path.display(), err);
+                }
+                return Ok(());
+            }
+        };
+        // sorted so that the order does not depend on the filesystem
+        let mut entries: Vec<PathBuf> = read_dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .collect();
+        entries.sort();
+
+        let ignore = Self::load_ignore_file(path);
+        let has_ignore = ignore.is_some();
+        if let Some(ignore) = ignore {
+            self.ignores.borrow_mut().push(ignore);
+        }
+        for entry in entries {
+            if runner::is_interrupted() || self.stopped.get() {
+                break;
+            }
+            if let Err(err) = self.accept(&entry, visit) {
+                eprintln!("{}", err);
+            }
+        }
+        if has_ignore {
+            self.ignores.borrow_mut().pop();
+        }
+        Ok(())
+    }
+
+    /// Parses `path/.depotignore` if there is one; invalid lines are reported and skipped.
+    fn load_ignore_file(path: &Path) -> Option<Gitignore> {
+        let file = path.join(IGNORE_FILE_NAME);
+        if !file.is_file() {
+            return None;
+        }
+        let mut builder = GitignoreBuilder::new(path);
+        if let Some(err) = builder.add(&file) {
+            eprintln!("warning: {}", err);
+        }
+        match builder.build() {
+            Ok(gitignore) => Some(gitignore),
+            Err(err) => {
+                eprintln!("warning: {}: {}", file.display(), err);
+                None
+            }
+        }
+    }
+
+    /// The innermost `.depotignore` with a matching pattern decides, then the global gitignore.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relpath) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        if relpath.as_os_str().is_empty() {
+            return false;
+        }
+        for ignore in self.ignores.borrow().iter().rev() {
+            match ignore.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        self.global_ignore
+            .as_ref()
+            .is_some_and(|ignore| ignore.matched(relpath, is_dir).is_ignore())
+    }
+
+    fn accept_project<F>(&self, path: &Path, visit: &mut F) -> GenericResult<()>
+    where
+        F: FnMut(&Path, &str) -> GenericResult<()>,
+    {
+        let relpath = path.strip_prefix(&self.root)?;
+        let relpath_str = Self::convert_path_to_str(relpath)?;
+        visit(path, relpath_str)
+    }
+
+    fn is_project(&self, path: &Path) -> bool {
+        self.condition.is_match(&self.root, path)
+    }
+
+    fn is_exclude(&self, path: &Path) -> bool {
+        if self.excluded_dirs.iter().any(|dir| dir == path) {
+            return true;
+        }
+        if let Ok(path) = path.strip_prefix(&self.root) {
+            if path == Path::new(utils::STATE_DIR_NAME) {
+                return true;
+            }
+            if let Some(path) = path.to_str() {
+                for exclude in &self.excludes {
+                    if exclude.is_match(path) {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns false if the directory was already entered through another path.
+    fn mark_visited(&self, metadata: &Metadata) -> bool {
+        self.visited
+            .borrow_mut()
+            .insert((metadata.dev(), metadata.ino()))
+    }
+
+    fn accept<F>(&self, path: &Path, visit: &mut F) -> GenericResult<()>
+    where
+        F: FnMut(&Path, &str) -> GenericResult<()>,
+    {
+        if self.is_exclude(path) {
+            return Ok(());
+        }
+        let is_symlink = fs::symlink_metadata(path)?.file_type().is_symlink();
+        if is_symlink && !self.follow_symlinks && path != self.root {
+            return Ok(());
+        }
+        // a dangling symlink is not worth a warning
+        let Ok(metadata) = fs::metadata(path) else {
+            return Ok(());
+        };
+
+        if self.is_ignored(path, metadata.is_dir()) {
+            return Ok(());
+        }
+        if metadata.is_dir() && !self.mark_visited(&metadata) {
+            return Ok(());
+        }
+        if self.is_project(path) {
+            self.accept_project(path, visit)?;
+            if !self.nested_projects {
+                return Ok(());
+            }
+        }
+        if metadata.is_dir() {
+            self.accept_directory(path, visit)?;
+        }
+        Ok(())
+    }
+
+    /// Calls `visit` with the absolute and root-relative path of every project, stopping on Ctrl-C.
+    pub fn run<F>(&self, mut visit: F) -> GenericResult<()>
+    where
+        F: FnMut(&Path, &str) -> GenericResult<()>,
+    {
+        self.accept(&self.root, &mut visit)?;
+        let unreadable = self.unreadable.get();
+        if unreadable > 0 {
+            eprintln!(
+                "warning: skipped {} unreadable director{}{}",
+                unreadable,
+                if unreadable == 1 { "y" } else { "ies" },
+                if utils::is_verbose() {
+                    ""
+                } else {
+                    " (use --verbose to list them)"
+                }
+            );
+        }
+        Ok(())
+    }
+}
@@ -7,14 +7,47 @@ pub const ABOUT: &str = "get overload from url";
 
 #[derive(Args)]
 pub struct Subcommand {
+    /// Print every matching overload with its priority and the pattern that matched
+    #[clap(short, long)]
+    all: bool,
+    /// Print the inheritance chain and which overload supplies each setting
+    #[clap(short, long)]
+    explain: bool,
     address: String,
 }
 
 impl super::Subcommand for Subcommand {
     fn run(&self, config: &Config, _store: impl Store) -> GenericResult<i32> {
-        let overload = config.overloads.find_overload_name(&self.address)?;
-        if let Some(name) = overload {
-            println!("{}", name);
+        let remote_url = config.parse.parse_url(&self.address)?;
+        if self.all {
+            let matches = config.overloads.find_overloads(&self.address, &remote_url);
+            if matches.is_empty() {
+                println!("(no overload)");
+            }
+            for m in matches {
+                println!("{}\t{}\t{}", m.overload.name, m.overload.priority, m.pattern);
+            }
+            return Ok(0);
+        }
+        let overload = config.overloads.find_overload(&self.address, &remote_url);
+        if self.explain {
+            let mut chain: Vec<&str> = overload.map_or(Vec::new(), |o| o.chain.iter().map(|n| n.as_str()).collect());
+            chain.push("(default)");
+            println!("{}", chain.join(" -> "));
+            let subcommands = &config.subcommands;
+            let sources = [
+                ("resolve", config.resolve.find_params_name(overload)),
+                ("get", subcommands.get.find_params_name(overload)),
+                ("create", subcommands.create.find_params_name(overload)),
+                ("move", subcommands.mv.find_params_name(overload)),
+            ];
+            for (name, source) in sources {
+                println!("{}\t{}", name, source.unwrap_or("(default)"));
+            }
+            return Ok(0);
+        }
+        if let Some(overload) = overload {
+            println!("{}", overload.name);
         } else {
             println!("(no overload)");
         }
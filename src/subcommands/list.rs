@@ -1,12 +1,27 @@
-use crate::config::fields_definition::FieldsDefinition;
-use crate::config::generator::Generator;
+use crate::config::classifiers::CompiledClassifiers;
+use crate::config::directory_condition::CompiledDirectoryCondition;
 use crate::config::pattern::Pattern;
+use crate::config::fields_definition::{Field, FieldDisplay};
 use crate::config::Config;
-use crate::error::{BuiltInCommandError, PathStringifyError};
-use crate::store::Store;
-use crate::utils::{CommandGenerator, Compilable, DirectoryMatcher, GenericResult, StringMatcher};
+use crate::error::{BuiltInCommandError, CustomError, PathStringifyError};
+use crate::exit_code;
+use crate::runner;
+use crate::store::{self, Store};
+use crate::table::{self, Cell, Table};
+use crate::template;
+use crate::utils::{CommandGenerator, Compilable, GenericResult, StringMatcher};
 use clap::Args;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::archive::ArchiveArea;
+use super::field_cache::FieldValueCache;
+use super::fs_visitor::FsVisitor;
 
 pub const ABOUT: &str = "list managed directory";
 
@@ -14,136 +29,310 @@ pub const ABOUT: &str = "list managed directory";
 pub struct Subcommand {
     #[clap(short, long, use_value_delimiter = true, default_values_t = [ "path".to_owned() ])]
     fields: Vec<String>,
+
+    /// Only projects matching `GLOB` (on the path), `FIELD=GLOB` or `FIELD~REGEX`; may be repeated
+    #[clap(long, value_name = "FILTER", parse(try_from_str = parse_filter))]
+    filter: Vec<FieldFilter>,
+
+    /// Only projects whose `host` field matches this glob
+    #[clap(long, value_name = "GLOB")]
+    host: Option<String>,
+
+    /// Only projects recognized as this overload, or one extending it
+    #[clap(long, value_name = "NAME")]
+    overload: Option<String>,
+
+    /// Only projects classified as this kind by `subcommands.list.classifiers`
+    #[clap(long, value_name = "KIND")]
+    kind: Option<String>,
+
+    /// Sorts by `path`, `mtime` or `field:<name>` instead of the walk order
+    #[clap(long, value_name = "KEY", parse(try_from_str = parse_sort))]
+    sort: Option<SortKey>,
+
+    /// Reverses the order
+    #[clap(short, long)]
+    reverse: bool,
+
+    /// Does not look for projects more than this many levels below the root
+    #[clap(long, value_name = "DEPTH")]
+    max_depth: Option<usize>,
+
+    /// Prints at most this many projects
+    #[clap(long, value_name = "COUNT")]
+    limit: Option<usize>,
+
+    /// Prints aligned columns with a header row; the default when stdout is a terminal
+    #[clap(long, overrides_with = "no-table")]
+    table: bool,
+
+    /// Prints tab-separated values even when stdout is a terminal
+    #[clap(long, overrides_with = "table")]
+    no_table: bool,
+
+    /// Computes every field instead of reusing cached values, and leaves the cache untouched
+    #[clap(long)]
+    no_cache: bool,
+}
+
+struct FieldFilter {
+    field: String,
+    pattern: Pattern,
+}
+
+fn parse_filter(filter: &str) -> GenericResult<FieldFilter> {
+    let name_len = filter
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(filter.len());
+    let (field, pattern) = match filter[name_len..].chars().next() {
+        Some('=') if name_len > 0 => (
+            &filter[..name_len],
+            Pattern::Glob(filter[name_len + 1..].to_owned()),
+        ),
+        Some('~') if name_len > 0 => (
+            &filter[..name_len],
+            Pattern::Regex(filter[name_len + 1..].to_owned()),
+        ),
+        _ => ("path", Pattern::Glob(filter.to_owned())),
+    };
+    pattern.compile()?;
+    Ok(FieldFilter {
+        field: field.to_owned(),
+        pattern,
+    })
+}
+
+enum SortKey {
+    Path,
+    Mtime,
+    Field(String),
 }
 
-struct FsVisitor<'a, Tcg: CommandGenerator, Tdm: DirectoryMatcher, Ts: Store> {
-    root: PathBuf,
+fn parse_sort(key: &str) -> GenericResult<SortKey> {
+    match key {
+        "path" => Ok(SortKey::Path),
+        "mtime" => Ok(SortKey::Mtime),
+        _ => match key.strip_prefix("field:") {
+            Some(name) if !name.is_empty() => Ok(SortKey::Field(name.to_owned())),
+            _ => Err(Box::new(CustomError::new(
+                "expected `path`, `mtime` or `field:<name>`",
+            ))),
+        },
+    }
+}
+
+struct Row {
+    rel_path: String,
+    mtime: Option<SystemTime>,
+    sort_value: String,
+    output: Vec<String>,
+}
+
+struct Lister<'a, Tcg: CommandGenerator, Ts: Store> {
+    config: &'a Config,
     store: Ts,
     cmdgen: Tcg,
-    excludes: Vec<Box<dyn StringMatcher>>,
-    condition: Tdm,
-    fields: Vec<&'a Generator>,
+    fields: &'a Vec<String>,
+    filters: Vec<(String, Box<dyn StringMatcher>)>,
+    overload: Option<&'a String>,
+    classifiers: CompiledClassifiers,
+    kind: Option<&'a String>,
+    sort: Option<&'a SortKey>,
+    cache: Option<RefCell<FieldValueCache>>,
 }
-impl<'a, Tcg: CommandGenerator, Tdm: DirectoryMatcher, Ts: Store> FsVisitor<'a, Tcg, Tdm, Ts> {
-    pub fn new<Tc: Compilable<Tdm>>(
-        root: &str,
+impl<'a, Tcg: CommandGenerator, Ts: Store> Lister<'a, Tcg, Ts> {
+    pub fn new(
+        config: &'a Config,
         store: Ts,
         cmdgen: Tcg,
-        exclude_patterns: &Vec<Pattern>,
-        condition: &Tc,
-        fields_def: &'a FieldsDefinition,
-        fields: &Vec<String>,
+        root_path: &str,
+        args: &'a Subcommand,
     ) -> GenericResult<Self> {
-        let mut excludes: Vec<Box<dyn StringMatcher>> = Vec::new();
-        for ptn in exclude_patterns {
-            excludes.push(ptn.compile()?)
-        }
-        let mut generators = Vec::<&Generator>::new();
-        for field_name in fields {
-            if let Some(def) = fields_def.get(field_name) {
-                generators.push(&def)
-            } else {
+        let mut filters = Vec::new();
+        for filter in &args.filter {
+            filters.push((filter.field.clone(), filter.pattern.compile()?));
+        }
+        if let Some(host) = &args.host {
+            filters.push(("host".to_owned(), Pattern::Glob(host.to_owned()).compile()?));
+        }
+
+        let mut names: Vec<&str> = args.fields.iter().map(|name| name.as_str()).collect();
+        names.extend(filters.iter().map(|(name, _)| name.as_str()));
+        if let Some(SortKey::Field(name)) = &args.sort {
+            names.push(name);
+        }
+        let fields = &config.subcommands.list.fields;
+        let mut uses_cache = false;
+        for name in names {
+            if fields.get(name).is_none() {
                 return Err(Box::new(BuiltInCommandError::new(&format!(
                     "field name not found: {}",
-                    field_name
+                    name
                 ))));
             }
+            // a cached field may only be reached as a dependency of the requested one
+            fields.walk(name, &mut |_, field| uses_cache |= field.cache.is_some());
         }
-        if generators.is_empty() {
-            if let Some(def) = fields_def.get("path") {
-                generators.push(def);
-            }
-        }
+        let cache = (uses_cache && !args.no_cache)
+            .then(|| RefCell::new(FieldValueCache::load(Path::new(root_path))));
+
         Ok(Self {
-            root: PathBuf::from(root),
-            store: store,
-            cmdgen: cmdgen,
-            excludes: excludes,
-            condition: condition.compile()?,
-            fields: generators,
+            config,
+            store,
+            cmdgen,
+            fields: &args.fields,
+            filters,
+            overload: args.overload.as_ref(),
+            classifiers: config.subcommands.list.classifiers.compile()?,
+            kind: args.kind.as_ref(),
+            sort: args.sort.as_ref(),
+            cache,
         })
     }
 
-    fn convert_path_to_str(path: &Path) -> GenericResult<&str> {
-        path.to_str()
-            .ok_or(Box::new(PathStringifyError::new("convert path failed")))
+    fn expand_field(&self, store: &Ts, relpath_str: &str, name: &str, field: &Field) -> GenericResult<String> {
+        let (Some(settings), Some(cache)) = (&field.cache, &self.cache) else {
+            return field.generator.expand(&self.cmdgen, store);
+        };
+        let mut paths = vec![PathBuf::from(store.get("DEPOT_LOCAL_PATH").unwrap_or_default())];
+        for path in &settings.invalidate_on {
+            paths.push(PathBuf::from(template::expand_template(store, path)));
+        }
+        let stamp = FieldValueCache::stamp(paths.iter().map(|path| path.as_path()));
+        // set by `field_value` before expanding this field
+        let dependencies: Vec<String> = field
+            .dependencies
+            .iter()
+            .map(|dependency| store.get(&store::field_var_name(dependency)).unwrap_or_default().to_owned())
+            .collect();
+        if let Some(value) = cache.borrow().get(relpath_str, name, &stamp, &dependencies, settings.ttl) {
+            return Ok(value.to_owned());
+        }
+        let value = field.generator.expand(&self.cmdgen, store)?;
+        cache.borrow_mut().insert(relpath_str, name, stamp, dependencies, value.clone());
+        Ok(value)
     }
 
-    fn accept_directory(&mut self, path: &Path) -> GenericResult<()> {
-        for entry in path.read_dir()? {
-            if let Ok(entry) = entry {
-                if let Err(err) = self.accept(&entry.path()) {
-                    eprintln!("{}", err);
-                }
+    fn save_cache(&self) {
+        if let Some(cache) = &self.cache {
+            if let Err(err) = cache.borrow_mut().save() {
+                eprintln!("warning: failed to save the field cache: {}", err);
             }
         }
-        Ok(())
     }
 
-    fn accept_file(&mut self, _: &Path) -> GenericResult<()> {
-        // ignore
-        Ok(())
+    /// Expands a field once per project, after the fields it refers to; errors are reported and leave the value empty.
+    fn field_value(
+        &self,
+        store: &Ts,
+        values: &mut HashMap<String, String>,
+        relpath_str: &str,
+        name: &str,
+    ) -> String {
+        self.config.subcommands.list.fields.walk(name, &mut |name, field| {
+            if values.contains_key(name) {
+                return;
+            }
+            let mut store = Cow::Borrowed(store);
+            for dependency in &field.dependencies {
+                let value = values.get(dependency).cloned().unwrap_or_default();
+                store.to_mut().set_field_value(dependency, value);
+            }
+            let value = self.expand_field(&store, relpath_str, name, field).unwrap_or_else(|err| {
+                eprintln!("{}: {}", relpath_str, err);
+                String::default()
+            });
+            values.insert(name.to_owned(), value);
+        });
+        values.get(name).cloned().unwrap_or_default()
     }
 
-    fn accept_project(&mut self, path: &Path) -> GenericResult<()> {
-        let path_str = Self::convert_path_to_str(path)?;
-        let relpath = path.strip_prefix(&self.root)?;
-        let relpath_str = Self::convert_path_to_str(relpath)?;
-        self.store
-            .set_local_path(path_str.to_owned(), relpath_str.to_owned());
-
-        let mut field_strs: Vec<String> = Vec::new();
-
-        for &field in &self.fields {
-            match field.expand(&self.cmdgen, &self.store) {
-                Ok(v) => field_strs.push(v),
-                Err(err) => eprintln!("{}", err),
+    /// Returns `None` if the project is filtered out.
+    fn evaluate(&self, root: &Path, path: &Path, relpath_str: &str) -> GenericResult<Option<Row>> {
+        let overload = self
+            .config
+            .overloads
+            .find_overload_for_directory(root, path);
+        if let Some(name) = self.overload {
+            if !overload.is_some_and(|overload| overload.chain.contains(name)) {
+                return Ok(None);
             }
         }
 
-        println!("{}", field_strs.join("\t"));
-        Ok(())
-    }
-
-    fn accept_exclude(&mut self, _: &Path) -> GenericResult<()> {
-        Ok(())
-    }
+        let kind = self.classifiers.classify(root, path).unwrap_or_default();
+        if self.kind.is_some_and(|expected| expected != kind) {
+            return Ok(None);
+        }
 
-    fn is_project(&mut self, path: &Path) -> bool {
-        self.condition.is_match(&self.root, path)
-    }
+        let path_str = path
+            .to_str()
+            .ok_or(Box::new(PathStringifyError::new("convert path failed")))?;
+        let mut store = self.store.clone();
+        store.set_local_path(path_str.to_owned(), relpath_str.to_owned());
+        store.set_project_kind(kind.to_owned());
+        if let Some(overload) = overload {
+            overload.export(&mut store);
+        }
 
-    fn is_exclude(&mut self, path: &Path) -> bool {
-        if let Ok(path) = path.strip_prefix(&self.root) {
-            if let Some(path) = path.to_str() {
-                for exclude in &self.excludes {
-                    if exclude.is_match(path) {
-                        return true;
-                    }
-                }
+        let mut values = HashMap::new();
+        for (name, matcher) in &self.filters {
+            if !matcher.is_match(&self.field_value(&store, &mut values, relpath_str, name)) {
+                return Ok(None);
             }
         }
-        false
-    }
 
-    fn accept(&mut self, path: &Path) -> GenericResult<()> {
-        if self.is_exclude(path) {
-            self.accept_exclude(path)
-        } else if self.is_project(path) {
-            self.accept_project(path)
-        } else if path.is_dir() {
-            self.accept_directory(path)
-        } else if path.is_file() {
-            self.accept_file(path)
-        } else {
-            Err(Box::new(BuiltInCommandError::new("unknown fs item")))
+        let mut output: Vec<String> = Vec::new();
+        for name in self.fields {
+            output.push(self.field_value(&store, &mut values, relpath_str, name));
         }
+        let sort_value = match self.sort {
+            Some(SortKey::Field(name)) => self.field_value(&store, &mut values, relpath_str, name),
+            _ => String::default(),
+        };
+        let mtime = match self.sort {
+            Some(SortKey::Mtime) => fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            _ => None,
+        };
+        Ok(Some(Row {
+            rel_path: relpath_str.to_owned(),
+            mtime,
+            sort_value,
+            output,
+        }))
     }
+}
+
+fn print_row(row: &Row) {
+    println!("{}", row.output.join("\t"));
+}
 
-    pub fn run(&mut self) -> GenericResult<()> {
-        let root = self.root.clone();
-        self.accept(&root)
+fn print_table(config: &Config, names: &[String], rows: impl Iterator<Item = Row>) {
+    let mut table = Table::new(names.iter().map(|name| name.to_uppercase()).collect());
+    let displays: Vec<Option<&FieldDisplay>> = names
+        .iter()
+        .map(|name| config.subcommands.list.fields.get(name).map(|field| &field.display))
+        .collect();
+    for (column, display) in displays.iter().enumerate() {
+        if let Some(display) = display {
+            table.style(column, display.align, display.max_width);
+        }
+    }
+    table.fit(table::terminal_width());
+    for row in rows {
+        let cells = row
+            .output
+            .into_iter()
+            .zip(&displays)
+            .map(|(text, display)| match display.and_then(|display| display.color) {
+                Some(color) => Cell::colored(text, color),
+                None => Cell::new(text),
+            })
+            .collect();
+        table.push(cells);
     }
+    table.print(table::color_enabled());
 }
 
 impl super::Subcommand for Subcommand {
@@ -151,16 +340,58 @@ impl super::Subcommand for Subcommand {
         let cmdgen = config.shell.compile(&store)?;
         let root_path = config.core.root.expand(&cmdgen, &store)?;
         store.set_root_path(root_path.to_owned());
-        let mut visitor = FsVisitor::new(
-            &root_path,
-            store,
-            cmdgen,
-            &config.subcommands.list.project.excludes,
-            &config.subcommands.list.project.condition,
-            &config.subcommands.list.fields,
-            &self.fields,
-        )?;
-        visitor.run()?;
-        return Ok(0);
+        let visitor: FsVisitor<CompiledDirectoryCondition> =
+            FsVisitor::for_project(&root_path, &config.subcommands.list.project, &config.overloads)?
+                .exclude_dir(ArchiveArea::new(config, &cmdgen, &store)?.path)
+                .max_depth(self.max_depth);
+        let lister = Lister::new(config, store, cmdgen, &root_path, self)?;
+        let root = PathBuf::from(&root_path);
+        let limit = self.limit.unwrap_or(usize::MAX);
+
+        // rows are printed as they are found unless they have to be aligned or reordered
+        let as_table = self.table || (!self.no_table && io::stdout().is_terminal());
+        let reorder = self.sort.is_some() || self.reverse;
+        let mut rows: Vec<Row> = Vec::new();
+        let mut found = 0;
+        visitor.run(|path, relpath| {
+            if !reorder && found >= limit {
+                visitor.stop();
+                return Ok(());
+            }
+            if let Some(row) = lister.evaluate(&root, path, relpath)? {
+                if as_table || reorder {
+                    rows.push(row);
+                } else {
+                    print_row(&row);
+                }
+                found += 1;
+                if !reorder && found >= limit {
+                    visitor.stop();
+                }
+            }
+            Ok(())
+        })?;
+        lister.save_cache();
+
+        match &self.sort {
+            Some(SortKey::Path) => rows.sort_by(|a, b| a.rel_path.cmp(&b.rel_path)),
+            Some(SortKey::Mtime) => rows.sort_by_key(|row| row.mtime),
+            Some(SortKey::Field(_)) => rows.sort_by(|a, b| a.sort_value.cmp(&b.sort_value)),
+            None => {}
+        }
+        if self.reverse {
+            rows.reverse();
+        }
+        let rows = rows.into_iter().take(limit);
+        if as_table {
+            print_table(config, &self.fields, rows);
+        } else {
+            rows.for_each(|row| print_row(&row));
+        }
+
+        if runner::is_interrupted() {
+            return Ok(exit_code::INTERRUPTED);
+        }
+        return Ok(exit_code::SUCCESS);
     }
 }
@@ -5,6 +5,8 @@ use crate::utils;
 use crate::utils::GenericResult;
 use clap::Args;
 
+use super::conflict::ConflictArgs;
+
 pub const ABOUT: &str = "move directory";
 
 #[derive(Args)]
@@ -13,6 +15,9 @@ pub struct Subcommand {
     resolve_source: bool,
     source: String,
     address: String,
+
+    #[clap(flatten)]
+    conflict: ConflictArgs,
 }
 
 impl super::Subcommand for Subcommand {
@@ -31,7 +36,10 @@ impl super::Subcommand for Subcommand {
             tmp_store.set_root_path(root_path.clone());
             tmp_store.set_remote_raw(&self.source);
             tmp_store.set_remote_url(&source_url);
-            let src_overload = config.overloads.find_overload_name(&self.source)?;
+            let src_overload = config.overloads.find_overload(&self.source, &source_url);
+            if let Some(overload) = src_overload {
+                overload.export(&mut tmp_store);
+            }
             let rel_path = config
                 .resolve
                 .expand_path(&cmdgen, &tmp_store, src_overload)?;
@@ -44,23 +52,29 @@ impl super::Subcommand for Subcommand {
             pre_command = &config.subcommands.mv.get_params(None).pre_command;
         }
 
-        let dst_overload = config.overloads.find_overload_name(&self.address)?;
         let remote_url = config.parse.parse_url(&self.address)?;
+        let dst_overload = config.overloads.find_overload(&self.address, &remote_url);
         store.set_remote_raw(&self.address);
         store.set_remote_url(&remote_url);
+        if let Some(overload) = dst_overload {
+            overload.export(&mut store);
+        }
 
         let rel_path = config.resolve.expand_path(&cmdgen, &store, dst_overload)?;
         let path = utils::concat_path(&root_path, &rel_path);
         store.set_local_path(path, rel_path);
 
-        let return_code = pre_command.execute(&cmdgen, &store)?;
+        let params = config.subcommands.mv.get_params(dst_overload);
+        if let Some(return_code) =
+            self.conflict
+                .check(params.on_conflict, &params.update_command, &cmdgen, &mut store)?
+        {
+            return Ok(return_code);
+        }
+
+        let return_code = pre_command.execute(&cmdgen, &mut store)?;
         if return_code == 0 {
-            config
-                .subcommands
-                .mv
-                .get_params(dst_overload)
-                .command
-                .execute(&cmdgen, &store)
+            params.command.execute(&cmdgen, &mut store)
         } else {
             Ok(return_code)
         }
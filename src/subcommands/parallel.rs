@@ -0,0 +1,46 @@
+use std::sync::Mutex;
+use std::thread;
+
+use crate::config::shell::CompiledShell;
+
+/// Number of worker threads for `count` items: `requested`, or the number of CPUs.
+pub fn jobs(requested: Option<usize>, count: usize) -> usize {
+    requested
+        .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .clamp(1, count.max(1))
+}
+
+/// Like `jobs`, and lets the commands of `cmdgen` run in the foreground only when they run one at a time.
+pub fn jobs_for_shell(cmdgen: &mut CompiledShell, requested: Option<usize>, count: usize) -> usize {
+    let jobs = jobs(requested, count);
+    // only a single command at a time may own the terminal
+    cmdgen.options.foreground = jobs == 1;
+    jobs
+}
+
+/// Calls `f` on every item using up to `jobs` threads and returns the results in the order of `items`.
+pub fn map<T, R, F>(items: Vec<T>, jobs: usize, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let next = queue.lock().unwrap().next();
+                let Some((index, item)) = next else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
@@ -0,0 +1,24 @@
+use crate::config::presets::PRESETS;
+use crate::config::Config;
+use crate::store::Store;
+use crate::utils::GenericResult;
+use clap::Args;
+
+pub const ABOUT: &str = "list built-in presets";
+
+#[derive(Args)]
+pub struct Subcommand {}
+
+impl super::Subcommand for Subcommand {
+    fn run(&self, config: &Config, _store: impl Store) -> GenericResult<i32> {
+        for preset in PRESETS {
+            let enabled = if config.presets.iter().any(|name| name == preset.name) {
+                "enabled"
+            } else {
+                "-"
+            };
+            println!("{}\t{}\t{}", preset.name, enabled, preset.description);
+        }
+        Ok(0)
+    }
+}
@@ -22,12 +22,15 @@ impl super::Subcommand for Subcommand {
 
         let root_path = config.core.root.expand(&cmdgen, &store)?;
         store.set_root_path(root_path.clone());
+        let overload = config.overloads.find_overload(&self.address, &remote_url);
+        if let Some(overload) = overload {
+            overload.export(&mut store);
+        }
 
         let rel_path = if let Some(template) = &self.template {
             let generator = Generator::Template(template.to_owned());
             generator.expand(&cmdgen, &store)?
         } else {
-            let overload = config.overloads.find_overload_name(&self.address)?;
             config.resolve.expand_path(&cmdgen, &store, overload)?
         };
         let path = utils::concat_path(&root_path, &rel_path);
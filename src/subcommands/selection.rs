@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::config::directory_condition::CompiledDirectoryCondition;
+use crate::config::overload::Overload;
+use crate::config::pattern::Pattern;
+use crate::config::Config;
+use crate::store::Store;
+use crate::utils::{Compilable, GenericResult};
+
+use super::archive::ArchiveArea;
+use super::fs_visitor::FsVisitor;
+
+/// Options selecting a subset of the projects found by the `list` walk.
+#[derive(Args)]
+pub struct ProjectSelection {
+    /// Only projects whose path relative to the root matches this glob
+    #[clap(long, value_name = "GLOB")]
+    filter: Option<String>,
+
+    /// Only projects recognized as this overload, or one extending it
+    #[clap(long, value_name = "NAME")]
+    overload: Option<String>,
+}
+
+pub struct SelectedProject<'a, Ts: Store> {
+    pub path: PathBuf,
+    pub rel_path: String,
+    pub overload: Option<&'a Overload>,
+    /// Has the local path, the project kind and the overload's variables set.
+    pub store: Ts,
+}
+
+impl ProjectSelection {
+    pub fn collect<'a, Ts: Store>(
+        &self,
+        config: &'a Config,
+        store: &Ts,
+        root_path: &str,
+    ) -> GenericResult<Vec<SelectedProject<'a, Ts>>> {
+        let filter = self
+            .filter
+            .as_ref()
+            .map(|filter| Pattern::Glob(filter.to_owned()).compile())
+            .transpose()?;
+        let classifiers = config.subcommands.list.classifiers.compile()?;
+        let visitor: FsVisitor<CompiledDirectoryCondition> =
+            FsVisitor::for_project(root_path, &config.subcommands.list.project, &config.overloads)?
+                .exclude_dir(ArchiveArea::new(config, &config.shell.compile(store)?, store)?.path);
+        let mut projects = Vec::new();
+        visitor.run(|path, rel_path| {
+            if filter
+                .as_ref()
+                .is_some_and(|filter| !filter.is_match(rel_path))
+            {
+                return Ok(());
+            }
+            let overload = config
+                .overloads
+                .find_overload_for_directory(visitor.root(), path);
+            if let Some(name) = &self.overload {
+                if !overload.is_some_and(|overload| overload.chain.contains(name)) {
+                    return Ok(());
+                }
+            }
+            let mut store = store.clone();
+            store.set_local_path(path.to_string_lossy().into_owned(), rel_path.to_owned());
+            let kind = classifiers.classify(visitor.root(), path).unwrap_or_default();
+            store.set_project_kind(kind.to_owned());
+            if let Some(overload) = overload {
+                overload.export(&mut store);
+            }
+            projects.push(SelectedProject {
+                path: path.to_path_buf(),
+                rel_path: rel_path.to_owned(),
+                overload,
+                store,
+            });
+            Ok(())
+        })?;
+        Ok(projects)
+    }
+}
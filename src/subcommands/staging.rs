@@ -0,0 +1,74 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::builtin;
+use crate::config::behavior::Behavior;
+use crate::store::Store;
+use crate::utils::{self, CommandGenerator, GenericResult};
+
+/// Runs `command` with `DEPOT_STAGING_PATH` set.
+///
+/// Without staging it is the local path itself. With staging it is a fresh path under
+/// `<root>/.depot/staging` which is renamed to the local path if the command succeeds
+/// and removed otherwise, so an interrupted command never leaves a partial project behind.
+pub fn execute<Tc: CommandGenerator, Ts: Store>(
+    staging: bool,
+    command: &Behavior,
+    cmdgen: &Tc,
+    store: &mut Ts,
+) -> GenericResult<i32> {
+    let local_path = store.get("DEPOT_LOCAL_PATH").unwrap_or_default().to_owned();
+    if !staging {
+        store.set_staging_path(local_path);
+        return command.execute(cmdgen, store);
+    }
+
+    let staging_path = staging_path(store);
+    if let Some(parent) = staging_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    store.set_staging_path(staging_path.to_string_lossy().into_owned());
+
+    let result = command.execute(cmdgen, store);
+    let staged = fs::symlink_metadata(&staging_path).is_ok();
+    match result {
+        Ok(0) if staged => match builtin::move_path(&staging_path, Path::new(&local_path)) {
+            Ok(()) => Ok(0),
+            Err(err) => {
+                discard(&staging_path);
+                Err(Box::new(err))
+            }
+        },
+        result => {
+            if staged {
+                discard(&staging_path);
+            }
+            result
+        }
+    }
+}
+
+fn staging_path<T: Store>(store: &T) -> PathBuf {
+    let root = store.get("DEPOT_ROOT_PATH").unwrap_or_default();
+    let name = store
+        .get("DEPOT_LOCAL_REL_PATH")
+        .unwrap_or_default()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<&str>>()
+        .join("%");
+    let dir = utils::concat_path(root, &format!("/{}/staging", utils::STATE_DIR_NAME));
+    PathBuf::from(dir).join(format!("{}.{}", name, process::id()))
+}
+
+fn discard(staging_path: &Path) {
+    let removed = if staging_path.is_dir() {
+        fs::remove_dir_all(staging_path)
+    } else {
+        fs::remove_file(staging_path)
+    };
+    if let Err(err) = removed {
+        eprintln!("failed to clean up {}: {}", staging_path.display(), err);
+    }
+}
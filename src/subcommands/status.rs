@@ -0,0 +1,167 @@
+use clap::Args;
+use serde_json::{json, Map, Value};
+
+use crate::config::checks::Checks;
+use crate::config::shell::CompiledShell;
+use crate::config::Config;
+use crate::exit_code;
+use crate::runner;
+use crate::store::Store;
+use crate::table::{self, Cell, Color, Table};
+use crate::utils::GenericResult;
+
+use super::parallel;
+use super::selection::ProjectSelection;
+
+pub const ABOUT: &str = "show the state of every project";
+
+#[derive(Args)]
+pub struct Subcommand {
+    #[clap(flatten)]
+    selection: ProjectSelection,
+
+    /// Only shows projects with a failing check
+    #[clap(long)]
+    dirty_only: bool,
+
+    /// Prints JSON instead of a table
+    #[clap(long)]
+    json: bool,
+
+    /// Number of projects checked in parallel [default: number of CPUs]
+    #[clap(short, long)]
+    jobs: Option<usize>,
+}
+
+struct ProjectStatus {
+    rel_path: String,
+    overload: Option<String>,
+    /// Check name and value, or `None` when the check could not run.
+    checks: Vec<(String, Option<String>)>,
+}
+impl ProjectStatus {
+    fn is_dirty(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|(_, value)| !value.as_deref().is_some_and(Checks::is_passing))
+    }
+}
+
+fn print_table(statuses: &[ProjectStatus]) {
+    let mut names: Vec<&str> = Vec::new();
+    for status in statuses {
+        for (name, _) in &status.checks {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+    }
+    let mut header = vec!["PROJECT".to_owned(), "OVERLOAD".to_owned()];
+    header.extend(names.iter().map(|name| name.to_uppercase()));
+    let mut table = Table::new(header);
+    for status in statuses {
+        let project_color = if status.is_dirty() {
+            Color::Yellow
+        } else {
+            Color::Green
+        };
+        let mut row = vec![
+            Cell::colored(status.rel_path.clone(), project_color),
+            Cell::new(status.overload.clone().unwrap_or_else(|| "-".to_owned())),
+        ];
+        for name in &names {
+            let value = status.checks.iter().find(|(n, _)| n == name);
+            row.push(match value {
+                None => Cell::new("-".to_owned()),
+                Some((_, None)) => Cell::colored("error".to_owned(), Color::Red),
+                Some((_, Some(value))) if Checks::is_passing(value) => {
+                    Cell::colored("ok".to_owned(), Color::Green)
+                }
+                Some((_, Some(value))) => Cell::colored(value.clone(), Color::Yellow),
+            });
+        }
+        table.push(row);
+    }
+    table.print(table::color_enabled());
+}
+
+fn print_json(statuses: &[ProjectStatus]) -> GenericResult<()> {
+    let projects: Vec<Value> = statuses
+        .iter()
+        .map(|status| {
+            let checks: Map<String, Value> = status
+                .checks
+                .iter()
+                .map(|(name, value)| (name.clone(), json!(value)))
+                .collect();
+            json!({
+                "path": status.rel_path,
+                "overload": status.overload,
+                "dirty": status.is_dirty(),
+                "checks": checks,
+            })
+        })
+        .collect();
+    println!("{}", serde_json::to_string_pretty(&projects)?);
+    Ok(())
+}
+
+impl super::Subcommand for Subcommand {
+    fn run(&self, config: &Config, mut store: impl Store) -> GenericResult<i32> {
+        let mut cmdgen = config.shell.compile(&store)?;
+        let root_path = config.core.root.expand(&cmdgen, &store)?;
+        store.set_root_path(root_path.clone());
+        // checks run unattended
+        cmdgen.options.foreground = false;
+
+        let projects = self.selection.collect(config, &store, &root_path)?;
+        let jobs = parallel::jobs(self.jobs, projects.len());
+        let projects: Vec<_> = projects
+            .into_iter()
+            .map(|project| {
+                let params = config.subcommands.status.get_params(project.overload);
+                let overload = project.overload.map(|overload| overload.name.clone());
+                (project, overload, params)
+            })
+            .collect();
+        let statuses = parallel::map(projects, jobs, |(project, overload, params)| {
+            let cmdgen = CompiledShell {
+                cwd: Some(project.path.clone()),
+                ..cmdgen.clone()
+            };
+            let mut checks = Vec::new();
+            for (name, generator) in params.checks.iter() {
+                if runner::is_interrupted() {
+                    break;
+                }
+                let value = match generator.expand(&cmdgen, &project.store) {
+                    Ok(value) => Some(value.trim().to_owned()),
+                    Err(err) => {
+                        eprintln!("{}: {}: {}", project.rel_path, name, err);
+                        None
+                    }
+                };
+                checks.push((name.to_owned(), value));
+            }
+            ProjectStatus {
+                rel_path: project.rel_path,
+                overload,
+                checks,
+            }
+        });
+        if runner::is_interrupted() {
+            return Ok(exit_code::INTERRUPTED);
+        }
+
+        let statuses: Vec<ProjectStatus> = statuses
+            .into_iter()
+            .filter(|status| !self.dirty_only || status.is_dirty())
+            .collect();
+        if self.json {
+            print_json(&statuses)?;
+        } else {
+            print_table(&statuses);
+        }
+        Ok(exit_code::SUCCESS)
+    }
+}
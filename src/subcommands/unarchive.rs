@@ -0,0 +1,127 @@
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use crate::builtin;
+use crate::config::archive_format::ArchiveFormat;
+use crate::config::shell::CompiledShell;
+use crate::config::Config;
+use crate::exit_code;
+use crate::store::Store;
+use crate::table::{self, Cell, Table};
+use crate::utils::{self, GenericResult};
+
+use super::archive::{self, ArchiveArea, Metadata};
+
+pub const ABOUT: &str = "restore an archived project to its resolved path";
+
+#[derive(Args)]
+pub struct Subcommand {
+    /// Address of the project to restore
+    #[clap(required_unless_present = "list", conflicts_with = "list")]
+    address: Option<String>,
+
+    /// Lists the archived projects instead
+    #[clap(long)]
+    list: bool,
+}
+
+fn print_entries(entries: Vec<(String, Metadata)>) {
+    let header = ["PATH", "ADDRESS", "OVERLOAD", "FORMAT"];
+    let mut table = Table::new(header.iter().map(|name| name.to_string()).collect());
+    table.fit(table::terminal_width());
+    for (_, metadata) in entries {
+        table.push(vec![
+            Cell::new(metadata.rel_path),
+            Cell::new(metadata.address),
+            Cell::new(metadata.overload.unwrap_or_else(|| "-".to_owned())),
+            Cell::new(metadata.format.name().to_owned()),
+        ]);
+    }
+    table.print(table::color_enabled());
+}
+
+fn is_not_found(err: &(dyn Error + 'static)) -> bool {
+    err.downcast_ref::<io::Error>()
+        .is_some_and(|err| err.kind() == io::ErrorKind::NotFound)
+}
+
+fn restore_data(
+    cmdgen: &CompiledShell,
+    format: ArchiveFormat,
+    data_path: &Path,
+    path: &Path,
+) -> GenericResult<()> {
+    match format {
+        ArchiveFormat::Directory => Ok(builtin::move_path(data_path, path)?),
+        ArchiveFormat::TarGz => {
+            fs::create_dir_all(path)?;
+            if let Err(err) = archive::run_tar(
+                cmdgen,
+                &[Path::new("-xzf"), data_path, Path::new("-C"), path],
+            ) {
+                let _ = fs::remove_dir_all(path);
+                return Err(err);
+            }
+            Ok(fs::remove_file(data_path)?)
+        }
+    }
+}
+
+impl super::Subcommand for Subcommand {
+    fn run(&self, config: &Config, mut store: impl Store) -> GenericResult<i32> {
+        let cmdgen = config.shell.compile(&store)?;
+        let root_path = config.core.root.expand(&cmdgen, &store)?;
+        store.set_root_path(root_path.clone());
+        let area = ArchiveArea::new(config, &cmdgen, &store)?;
+
+        let Some(address) = &self.address else {
+            print_entries(area.entries()?);
+            return Ok(exit_code::SUCCESS);
+        };
+        let (resolved_path, rel_path, _) =
+            archive::resolve_address(config, &cmdgen, &mut store, address)?;
+
+        // the entry is named after the path it was archived from, which may differ if `resolve` changed since
+        let name = ArchiveArea::entry_name(&rel_path);
+        let entry = match area.read_metadata(&name) {
+            Ok(metadata) => Some((name, metadata)),
+            Err(err) if !is_not_found(err.as_ref()) => return Err(err),
+            Err(_) => area
+                .entries()?
+                .into_iter()
+                .find(|(_, metadata)| &metadata.address == address),
+        };
+        let Some((name, metadata)) = entry else {
+            eprintln!("{}: not archived in {}", address, area.path.display());
+            return Ok(exit_code::FAILURE);
+        };
+        // restored where it was archived from, even if the address resolves elsewhere now
+        let path = PathBuf::from(utils::concat_path(&root_path, &metadata.rel_path));
+        if path != resolved_path {
+            eprintln!(
+                "warning: restoring to {}, where it was archived from; {} now resolves to {}",
+                path.display(),
+                address,
+                resolved_path.display()
+            );
+        }
+        if fs::symlink_metadata(&path).is_ok() {
+            eprintln!("{}: already exists", path.display());
+            return Ok(exit_code::CONFLICT);
+        }
+
+        restore_data(
+            &cmdgen,
+            metadata.format,
+            &area.data_path(&name, metadata.format),
+            &path,
+        )?;
+        fs::remove_file(area.metadata_path(&name))?;
+        println!("{}", path.display());
+        Ok(exit_code::SUCCESS)
+    }
+}
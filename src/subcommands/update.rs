@@ -0,0 +1,201 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::config::behavior::Behavior;
+use crate::config::shell::CompiledShell;
+use crate::config::{Config, UpdateParams};
+use crate::exit_code;
+use crate::runner;
+use crate::store::Store;
+use crate::table::{self, Cell, Color, Table};
+use crate::utils::{self, GenericResult};
+
+use super::parallel;
+use super::selection::ProjectSelection;
+
+pub const ABOUT: &str = "update projects with their overload's update command";
+
+#[derive(Args)]
+pub struct Subcommand {
+    /// Address of the project to update
+    #[clap(
+        required_unless_present_any = &["filter", "overload", "all"],
+        conflicts_with_all = &["filter", "overload"]
+    )]
+    address: Option<String>,
+
+    #[clap(flatten)]
+    selection: ProjectSelection,
+
+    /// Updates every project found by `list`
+    #[clap(long, conflicts_with_all = &["address", "filter", "overload"])]
+    all: bool,
+
+    /// Number of projects updated in parallel [default: number of CPUs]
+    #[clap(short, long)]
+    jobs: Option<usize>,
+}
+
+struct Target<'a, Ts: Store> {
+    path: PathBuf,
+    rel_path: String,
+    store: Ts,
+    params: &'a UpdateParams,
+}
+
+enum Status {
+    Updated,
+    Failed(i32),
+    Skipped(&'static str),
+}
+
+impl Subcommand {
+    fn find_by_address<'a, Ts: Store>(
+        &self,
+        config: &'a Config,
+        cmdgen: &CompiledShell,
+        mut store: Ts,
+        address: &str,
+    ) -> GenericResult<Vec<Target<'a, Ts>>> {
+        let remote_url = config.parse.parse_url(address)?;
+        let overload = config.overloads.find_overload(address, &remote_url);
+        store.set_remote_raw(address);
+        store.set_remote_url(&remote_url);
+        if let Some(overload) = overload {
+            overload.export(&mut store);
+        }
+
+        let root_path = store.get("DEPOT_ROOT_PATH").unwrap_or_default().to_owned();
+        let rel_path = config.resolve.expand_path(cmdgen, &store, overload)?;
+        let path = utils::concat_path(&root_path, &rel_path);
+        store.set_local_path(path.clone(), rel_path.clone());
+        Ok(vec![Target {
+            path: PathBuf::from(path),
+            rel_path,
+            store,
+            params: config.subcommands.update.get_params(overload),
+        }])
+    }
+}
+
+fn update<Ts: Store>(cmdgen: &CompiledShell, target: Target<'_, Ts>) -> Status {
+    let Target {
+        path,
+        rel_path,
+        mut store,
+        params,
+    } = target;
+    if runner::is_interrupted() {
+        return Status::Skipped("interrupted");
+    }
+    if matches!(params.command, Behavior::NotSupported) {
+        return Status::Skipped("not supported");
+    }
+    if !path.is_dir() {
+        eprintln!("{}: not found", path.display());
+        return Status::Failed(exit_code::FAILURE);
+    }
+    let cmdgen = CompiledShell {
+        cwd: Some(path),
+        ..cmdgen.clone()
+    };
+    match params.command.execute(&cmdgen, &mut store) {
+        Ok(exit_code::SUCCESS) => Status::Updated,
+        Ok(code) => Status::Failed(code),
+        Err(err) => {
+            eprintln!("{}: {}", rel_path, err);
+            Status::Failed(exit_code::FAILURE)
+        }
+    }
+}
+
+fn print_summary(results: &[(String, Status)]) {
+    let (mut updated, mut failed, mut skipped) = (0, 0, 0);
+    let header = ["STATUS", "PROJECT", "DETAIL"];
+    let mut table = Table::new(header.iter().map(|name| name.to_string()).collect());
+    table.fit(table::terminal_width());
+    for (rel_path, status) in results {
+        let (label, color, detail) = match status {
+            Status::Updated => {
+                updated += 1;
+                ("updated", Color::Green, String::new())
+            }
+            Status::Failed(code) => {
+                failed += 1;
+                ("failed", Color::Red, format!("exit {}", code))
+            }
+            Status::Skipped(reason) => {
+                skipped += 1;
+                ("skipped", Color::Yellow, reason.to_string())
+            }
+        };
+        table.push(vec![
+            Cell::colored(label.to_owned(), color),
+            Cell::new(rel_path.clone()),
+            Cell::new(detail),
+        ]);
+    }
+    table.print(table::color_enabled());
+    println!(
+        "{} updated, {} failed, {} skipped",
+        updated, failed, skipped
+    );
+}
+
+impl super::Subcommand for Subcommand {
+    fn run(&self, config: &Config, mut store: impl Store) -> GenericResult<i32> {
+        let mut cmdgen = config.shell.compile(&store)?;
+        let root_path = config.core.root.expand(&cmdgen, &store)?;
+        store.set_root_path(root_path.clone());
+
+        let targets = match &self.address {
+            Some(address) => self.find_by_address(config, &cmdgen, store, address)?,
+            None => self
+                .selection
+                .collect(config, &store, &root_path)?
+                .into_iter()
+                .map(|project| Target {
+                    path: project.path,
+                    rel_path: project.rel_path,
+                    store: project.store,
+                    params: config.subcommands.update.get_params(project.overload),
+                })
+                .collect(),
+        };
+        if targets.is_empty() {
+            eprintln!("no projects to update");
+            return Ok(exit_code::SUCCESS);
+        }
+        let single = self.address.is_some();
+
+        let jobs = parallel::jobs_for_shell(&mut cmdgen, self.jobs, targets.len());
+
+        let names: Vec<String> = targets.iter().map(|t| t.rel_path.clone()).collect();
+        let statuses = parallel::map(targets, jobs, |target| {
+            if jobs > 1 {
+                eprintln!("updating {}", target.rel_path);
+            }
+            update(&cmdgen, target)
+        });
+        let results: Vec<(String, Status)> = names.into_iter().zip(statuses).collect();
+
+        if !single {
+            print_summary(&results);
+        }
+        if runner::is_interrupted() {
+            return Ok(exit_code::INTERRUPTED);
+        }
+        Ok(match &results[0].1 {
+            Status::Failed(code) if single => *code,
+            Status::Skipped(_) if single => exit_code::NOT_SUPPORTED,
+            _ if results
+                .iter()
+                .any(|(_, status)| matches!(status, Status::Failed(_))) =>
+            {
+                exit_code::FAILURE
+            }
+            _ => exit_code::SUCCESS,
+        })
+    }
+}
@@ -0,0 +1,191 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::os::unix::io::AsRawFd;
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    #[serde(alias = "red")]
+    Red,
+    #[serde(alias = "green")]
+    Green,
+    #[serde(alias = "yellow")]
+    Yellow,
+    #[serde(alias = "blue")]
+    Blue,
+    #[serde(alias = "magenta")]
+    Magenta,
+    #[serde(alias = "cyan")]
+    Cyan,
+}
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Color::Red => "31",
+            Color::Green => "32",
+            Color::Yellow => "33",
+            Color::Blue => "34",
+            Color::Magenta => "35",
+            Color::Cyan => "36",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    #[serde(alias = "left")]
+    Left,
+    #[serde(alias = "right")]
+    Right,
+}
+
+/// Colors are used when stdout is a terminal and `NO_COLOR` is not set.
+pub fn color_enabled() -> bool {
+    io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+/// Width of the terminal on stdout, or `COLUMNS` when it is not a terminal.
+pub fn terminal_width() -> Option<usize> {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::ioctl(io::stdout().as_raw_fd(), libc::TIOCGWINSZ, &mut size) };
+    if result == 0 && size.ws_col > 0 {
+        return Some(size.ws_col as usize);
+    }
+    env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok())
+}
+
+pub struct Cell {
+    pub text: String,
+    pub color: Option<Color>,
+}
+impl Cell {
+    pub fn new(text: String) -> Self {
+        Self { text, color: None }
+    }
+
+    pub fn colored(text: String, color: Color) -> Self {
+        Self {
+            text,
+            color: Some(color),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct ColumnStyle {
+    align: Align,
+    max_width: Option<usize>,
+}
+
+const SEPARATOR: &str = "  ";
+/// Columns are not shrunk below this width to fit the terminal.
+const MIN_WIDTH: usize = 3;
+
+/// Columns separated by two spaces, with a header row. Columns are left-aligned unless styled otherwise,
+/// and values wider than their column are cut off with `…`.
+pub struct Table {
+    header: Vec<String>,
+    styles: Vec<ColumnStyle>,
+    total_width: Option<usize>,
+    rows: Vec<Vec<Cell>>,
+}
+impl Table {
+    pub fn new(header: Vec<String>) -> Self {
+        Self {
+            styles: vec![ColumnStyle::default(); header.len()],
+            header,
+            total_width: None,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn style(&mut self, column: usize, align: Align, max_width: Option<usize>) {
+        if let Some(style) = self.styles.get_mut(column) {
+            *style = ColumnStyle { align, max_width };
+        }
+    }
+
+    /// Shrinks the widest columns until lines fit in `total_width` characters.
+    pub fn fit(&mut self, total_width: Option<usize>) {
+        self.total_width = total_width;
+    }
+
+    pub fn push(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.header.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.text.chars().count());
+            }
+        }
+        for (width, style) in widths.iter_mut().zip(&self.styles) {
+            if let Some(max_width) = style.max_width {
+                *width = (*width).min(max_width.max(1));
+            }
+        }
+        if let Some(total_width) = self.total_width {
+            let separators = SEPARATOR.len() * widths.len().saturating_sub(1);
+            while widths.iter().sum::<usize>() + separators > total_width {
+                match widths.iter_mut().filter(|width| **width > MIN_WIDTH).max() {
+                    Some(widest) => *widest -= 1,
+                    None => break,
+                }
+            }
+        }
+        widths
+    }
+
+    fn truncate(text: &str, width: usize) -> String {
+        if text.chars().count() <= width {
+            return text.to_owned();
+        }
+        let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+        truncated.push('…');
+        truncated
+    }
+
+    fn render_row<'a>(
+        &self,
+        cells: impl Iterator<Item = (&'a str, Option<Color>)>,
+        widths: &[usize],
+        color: bool,
+    ) -> String {
+        let mut line = String::new();
+        for (index, ((text, cell_color), width)) in cells.zip(widths).enumerate() {
+            if index > 0 {
+                line.push_str(SEPARATOR);
+            }
+            let text = Self::truncate(text, *width);
+            let padding = " ".repeat(width - text.chars().count());
+            let align = self.styles.get(index).map(|style| style.align).unwrap_or_default();
+            if align == Align::Right {
+                line.push_str(&padding);
+            }
+            match cell_color.filter(|_| color) {
+                Some(cell_color) => {
+                    line.push_str(&format!("\x1b[{}m{}\x1b[0m", cell_color.code(), text))
+                }
+                None => line.push_str(&text),
+            }
+            if align == Align::Left {
+                line.push_str(&padding);
+            }
+        }
+        line.trim_end().to_owned()
+    }
+
+    pub fn print(&self, color: bool) {
+        let widths = self.widths();
+        let header = self.header.iter().map(|h| (h.as_str(), None));
+        println!("{}", self.render_row(header, &widths, color));
+        for row in &self.rows {
+            let cells = row.iter().map(|cell| (cell.text.as_str(), cell.color));
+            println!("{}", self.render_row(cells, &widths, color));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{store::Store, utils::GenericResult, error::CustomError};
+use crate::{store::{self, Store}, utils::GenericResult, error::CustomError};
 
 fn call_func<T: Store>(store: &T, args: &Vec<&str>) -> GenericResult<String> {
     match *args.get(0).ok_or(CustomError::new("too few arguments"))? {
@@ -18,6 +18,10 @@ fn call_func<T: Store>(store: &T, args: &Vec<&str>) -> GenericResult<String> {
                 return Ok(String::default());
             }
         },
+        "field" => {
+            let name = *args.get(1).ok_or(CustomError::new("too few arguments"))?;
+            Ok(store.get(&store::field_var_name(name)).unwrap_or_default().to_owned())
+        },
         _ => Err(Box::new(CustomError::new("unknown function"))),
     }
 }
@@ -71,3 +75,16 @@ pub fn expand_template<T: Store>(store: &T, template: &str) -> String {
 
     result
 }
+
+/// Evaluates `lhs == rhs`, `lhs != rhs` or a single value, where each side is expanded as a template.
+/// A single value is true unless it is empty, `0` or `false`.
+pub fn evaluate_condition<T: Store>(store: &T, condition: &str) -> bool {
+    if let Some((lhs, rhs)) = condition.split_once("!=") {
+        expand_template(store, lhs.trim()) != expand_template(store, rhs.trim())
+    } else if let Some((lhs, rhs)) = condition.split_once("==") {
+        expand_template(store, lhs.trim()) == expand_template(store, rhs.trim())
+    } else {
+        let value = expand_template(store, condition.trim());
+        !matches!(value.trim(), "" | "0" | "false")
+    }
+}
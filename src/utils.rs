@@ -2,13 +2,32 @@ use std::error::Error;
 use std::env;
 use std::path::Path;
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
+use url::Url;
+
+use crate::error::CustomError;
+use crate::runner::ProcessOptions;
 use crate::store::Store;
 pub type GenericError = Box<dyn Error + Sync + Send + 'static>;
 pub type GenericResult<T> = Result<T, GenericError>;
 
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
 pub trait StringMatcher {
     fn is_match(&self, str: &str) -> bool;
+    fn is_match_address(&self, address: &str, _url: &Url) -> bool {
+        self.is_match(address)
+    }
 }
 
 pub trait DirectoryMatcher {
@@ -17,12 +36,22 @@ pub trait DirectoryMatcher {
 
 pub trait CommandGenerator {
     fn generate<T: Store>(&self, store: &T) -> Command;
+    fn options(&self) -> ProcessOptions {
+        ProcessOptions::default()
+    }
+    /// Working directory for steps that do not set their own `cwd`.
+    fn current_dir(&self) -> Option<&Path> {
+        None
+    }
 }
 
 pub trait Compilable<T> {
     fn compile(&self) -> GenericResult<T>;
 }
 
+/// Directory under the root where depot keeps its own state; never listed as a project.
+pub const STATE_DIR_NAME: &str = ".depot";
+
 pub fn concat_path(base: &str, path: &str) -> String {
     let mut result = base.to_owned();
     let is_absolute = result.starts_with("/");
@@ -80,3 +109,48 @@ pub fn trim_end(mut str: String) -> String {
     str.truncate(trimed.len());
     str
 }
+
+pub fn parse_duration(text: &str) -> GenericResult<Duration> {
+    let text = text.trim();
+    let invalid = || CustomError::new(&format!("invalid duration: {}", text));
+    let unit_index = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let value: u64 = text[..unit_index].parse().map_err(|_| invalid())?;
+    let duration = match &text[unit_index..] {
+        "ms" => Duration::from_millis(value),
+        "" | "s" => Duration::from_secs(value),
+        "m" => Duration::from_secs(value.checked_mul(60).ok_or_else(invalid)?),
+        "h" => Duration::from_secs(value.checked_mul(60 * 60).ok_or_else(invalid)?),
+        "d" => Duration::from_secs(value.checked_mul(60 * 60 * 24).ok_or_else(invalid)?),
+        _ => return Err(Box::new(invalid())),
+    };
+    Ok(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration(" 10m ").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert_eq!(parse_duration("2d").unwrap(), Duration::from_secs(172800));
+    }
+
+    #[test]
+    fn parse_duration_rejects_invalid() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("1w").is_err());
+        assert!(parse_duration("-1s").is_err());
+    }
+
+    #[test]
+    fn parse_duration_rejects_overflow() {
+        assert!(parse_duration("999999999999999999d").is_err());
+        assert!(parse_duration("999999999999999999h").is_err());
+        assert!(parse_duration("999999999999999999m").is_err());
+        assert!(parse_duration("99999999999999999999s").is_err());
+    }
+}